@@ -0,0 +1,20 @@
+//! Records the version of the magic-wormhole library in use, for
+//! `version()`: `Cargo.toml` only names the oldest one accepted, the lock
+//! file the one built.
+
+use std::path::Path;
+
+fn main() {
+    let lockfile = Path::new(&std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lockfile.display());
+    let version = std::fs::read_to_string(&lockfile)
+        .ok()
+        .and_then(|lock| {
+            lock.split("[[package]]")
+                .find(|package| package.lines().any(|line| line.trim() == "name = \"magic-wormhole\""))
+                .and_then(|package| package.lines().find_map(|line| line.trim().strip_prefix("version = ")))
+                .map(|version| version.trim_matches('"').to_owned())
+        })
+        .unwrap_or_else(|| "unknown".to_owned());
+    println!("cargo:rustc-env=MAGIC_WORMHOLE_VERSION={}", version);
+}
@@ -0,0 +1,44 @@
+use magic_wormhole::transit::Abilities;
+use wasm_bindgen::prelude::*;
+
+/// The transit connection types offered to the peer.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitMode {
+    /// Only connect through the transit relay.
+    RelayOnly,
+    /// Offer direct connections and fall back to the relay.
+    DirectIfPossible,
+}
+
+impl From<TransitMode> for Abilities {
+    fn from(mode: TransitMode) -> Self {
+        match mode {
+            TransitMode::RelayOnly => Abilities::FORCE_RELAY,
+            TransitMode::DirectIfPossible => Abilities::ALL,
+        }
+    }
+}
+
+/// Builds `Abilities` from a list of ability names as they appear in the
+/// transit hints, e.g. `["direct-tcp-v1", "relay-v1"]`.
+pub(crate) fn parse_abilities(names: &js_sys::Array) -> Result<Abilities, JsValue> {
+    let mut abilities = Abilities {
+        direct_tcp_v1: false,
+        relay_v1: false,
+    };
+
+    for name in names.iter() {
+        match name.as_string().as_deref() {
+            Some("direct-tcp-v1") => abilities.direct_tcp_v1 = true,
+            Some("relay-v1") => abilities.relay_v1 = true,
+            _ => return Err(JsValue::from_str(&format!("Unknown transit ability: {:?}", name))),
+        }
+    }
+
+    if !abilities.direct_tcp_v1 && !abilities.relay_v1 {
+        return Err(JsValue::from_str("At least one transit ability is required"));
+    }
+
+    Ok(abilities)
+}
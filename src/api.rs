@@ -0,0 +1,93 @@
+//! The transfers as a Rust API, for Rust frontends (Yew, Leptos, ...) that
+//! use this crate as a library rather than through JS values.
+//!
+//! A transfer reports to an `Events` implementation and is controlled with
+//! `Controls`. Everything else is configured on `ClientConfig` as for JS.
+//! Nothing here depends on the DOM: browser files are sent with
+//! `ClientConfig::send_web_file` from the bindings, and the adapters in
+//! `stream` turn JS streams into `AsyncRead`s and `AsyncWrite`s.
+//!
+//! ```ignore
+//! let controls = Controls::default();
+//! let result = config.receive_to_memory(code, None, Rc::new(MyEvents), &controls).await;
+//! ```
+
+use std::rc::Rc;
+
+use futures::io::{AsyncRead, AsyncWrite};
+
+use crate::cancel::Cancel;
+use crate::context::TransferContext;
+use crate::metadata::FileMetadata;
+use crate::pause::Pause;
+use crate::{ClientConfig, ReceiveResult, TransferStats};
+
+pub use crate::events::Events;
+pub use crate::phase::Phase;
+pub use crate::stats::{EntryProgress, Progress};
+pub use crate::timeout::Timeout;
+pub use crate::trace::Direction;
+
+/// Cancels or pauses a running transfer from the outside. Clones control
+/// the same transfer.
+#[derive(Clone, Default)]
+pub struct Controls {
+    cancel: Cancel,
+    pause: Pause,
+}
+
+impl Controls {
+    /// Aborts the transfer, see `WormholeSession.close`.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.pause.set_paused(paused);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause.is_paused()
+    }
+
+    pub(crate) fn context(&self, events: Rc<dyn Events>, expected_sha256: Option<String>) -> TransferContext {
+        TransferContext {
+            events,
+            on_verifier: None,
+            cancel: self.cancel.clone(),
+            pause: self.pause.clone(),
+            expected_sha256,
+        }
+    }
+}
+
+/// The transfers return `None` once they failed or were cancelled, after
+/// reporting why to `events`.
+impl ClientConfig {
+    /// Sends `size` bytes read from `reader` as a file called `name`.
+    pub async fn send_reader<R: AsyncRead + Unpin>(&self, reader: &mut R, size: u64, name: String, events: Rc<dyn Events>, controls: &Controls) -> Option<TransferStats> {
+        self.send_via_wormhole(reader, size, name, &FileMetadata::default(), &controls.context(events, None)).await
+    }
+
+    /// Receives the file sent with `code` into memory. If `expected_sha256`
+    /// is given, the transfer fails unless the data has that SHA-256 (as hex).
+    pub async fn receive_to_memory(&self, code: String, expected_sha256: Option<String>, events: Rc<dyn Events>, controls: &Controls) -> Option<ReceiveResult> {
+        self.receive_file(code, &controls.context(events, expected_sha256)).await
+    }
+
+    /// Receives the file sent with `code` into `writer`, like a
+    /// `stream::StreamWriter`, which is closed once all data is written.
+    /// Compression is not used, so `writer` gets the file as sent.
+    pub async fn receive_to_writer<W: AsyncWrite + Unpin>(&self, code: String, writer: W, events: Rc<dyn Events>, controls: &Controls) -> Option<TransferStats> {
+        let context = controls.context(events, None);
+        let instrumented = self.instrumented(&context, Direction::In);
+        let context = instrumented.as_ref().unwrap_or(&context);
+        let received = self.uncompressed().receive_into(code, context, |_, _| async move { Ok(writer) }).await?;
+        context.events.phase(Phase::Done);
+        Some(received.stats)
+    }
+}
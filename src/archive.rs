@@ -0,0 +1,372 @@
+//! The zip archives files and directories are sent as.
+//!
+//! Archives are written as they are sent, so every entry is stored with a
+//! data descriptor after its data for the CRC-32, which is only known once
+//! the data went out. The sizes are known from the start and are in the
+//! local headers as well, which lets a receiver tell where an entry ends
+//! before the central directory arrives. Empty entries have nothing to
+//! wait for and no data descriptor, so that a size of zero is never
+//! mistaken for one given only after the data.
+
+use std::io::{self, Cursor, Read};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::AsyncRead;
+use wasm_bindgen::prelude::*;
+use zip::result::ZipResult;
+use zip::ZipArchive;
+
+use crate::metadata::EntryMetadata;
+use crate::offer::Selection;
+use crate::stats::EntryProgress;
+
+/// The fixed parts of a local file header, a data descriptor, a central
+/// directory header, the end of central directory record and its zip64
+/// locator and record. These and the values below are shared with the
+/// readers in `extract` and `unzip`.
+pub(crate) const LOCAL_HEADER_LEN: u64 = 30;
+pub(crate) const DATA_DESCRIPTOR_LEN: u64 = 16;
+pub(crate) const CENTRAL_HEADER_LEN: u64 = 46;
+pub(crate) const END_RECORD_LEN: u64 = 22;
+pub(crate) const ZIP64_LOCATOR_LEN: u64 = 20;
+pub(crate) const ZIP64_END_RECORD_LEN: u64 = 56;
+pub(crate) const MAX_COMMENT_LEN: u64 = 0xffff;
+
+pub(crate) const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+pub(crate) const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x0807_4b50;
+pub(crate) const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+pub(crate) const END_RECORD_SIGNATURE: u32 = 0x0605_4b50;
+pub(crate) const ZIP64_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+pub(crate) const ZIP64_END_RECORD_SIGNATURE: u32 = 0x0606_4b50;
+
+/// The header id of the extra field with the 64 bit sizes and offset.
+pub(crate) const ZIP64_EXTRA_ID: u16 = 0x0001;
+
+/// The compression methods entries are read with.
+pub(crate) const STORED: u16 = 0;
+pub(crate) const DEFLATED: u16 = 8;
+
+/// Version 2.0, needed for data descriptors.
+const VERSION: u16 = 20;
+/// The CRC-32 and sizes follow the data.
+pub(crate) const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+/// The name is UTF-8 rather than code page 437.
+const FLAG_UTF8: u16 = 0x0800;
+/// 1980-01-01 00:00, the earliest DOS date; browsers give no dates for
+/// files in directories.
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// A single file in a received archive.
+#[wasm_bindgen]
+pub struct ArchiveEntry {
+    pub(crate) name: String,
+    pub(crate) data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl ArchiveEntry {
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// The content of the file, copied into a new array on each call.
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> js_sys::Uint8Array {
+        js_sys::Uint8Array::from(&self.data[..])
+    }
+}
+
+/// A file to pack: its name in the archive, size and content.
+pub struct PackEntry<R> {
+    pub name: String,
+    pub size: u64,
+    pub reader: R,
+}
+
+/// Where an entry ended up, for its central directory header.
+struct Packed {
+    offset: u32,
+    crc: u32,
+}
+
+enum PackState<R> {
+    /// The local header of the entry at this index is next, or the central
+    /// directory after the last one.
+    Header(usize),
+    Data(usize, R, flate2::Crc),
+    Done,
+}
+
+/// Packs `PackEntry`s into a zip archive as it is read, reading each file
+/// only when its turn comes. Entries are stored uncompressed, most files
+/// worth sending are compressed already.
+///
+/// Nothing but the headers is buffered, so archives of any size can be
+/// sent, up to the 4 GiB that zip allows without its zip64 extensions.
+pub struct ZipStream<R> {
+    names: Vec<(String, u32)>,
+    readers: Vec<Option<R>>,
+    packed: Vec<Packed>,
+    /// Header bytes to go out before anything else.
+    buffer: Vec<u8>,
+    buffer_offset: usize,
+    /// How much has gone out, for the offsets in the central directory.
+    position: u64,
+    size: u64,
+    state: PackState<R>,
+}
+
+impl<R: AsyncRead + Unpin> ZipStream<R> {
+    pub fn new(entries: Vec<PackEntry<R>>) -> Result<Self, String> {
+        let layout = Layout::new(&entries.iter().map(|entry| EntryMetadata { name: entry.name.clone(), size: entry.size }).collect::<Vec<_>>());
+        if entries.len() > u16::MAX as usize || layout.as_ref().is_none_or(|layout| layout.size > u32::MAX as u64) {
+            return Err("Archives of more than 4 GiB or 65535 files are not supported".to_owned());
+        }
+        if let Some(entry) = entries.iter().find(|entry| entry.name.len() > u16::MAX as usize) {
+            return Err(format!("The name {} is too long for an archive", entry.name));
+        }
+        let mut names = Vec::with_capacity(entries.len());
+        let mut readers = Vec::with_capacity(entries.len());
+        for entry in entries {
+            names.push((entry.name, entry.size as u32));
+            readers.push(Some(entry.reader));
+        }
+        Ok(ZipStream {
+            packed: Vec::with_capacity(names.len()),
+            names,
+            readers,
+            buffer: Vec::new(),
+            buffer_offset: 0,
+            position: 0,
+            size: layout.map_or(0, |layout| layout.size),
+            state: PackState::Header(0),
+        })
+    }
+
+    /// The size of the whole archive.
+    /// The size of the whole archive.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn local_header(&mut self, index: usize) {
+        let (name, size) = &self.names[index];
+        let buffer = &mut self.buffer;
+        put_u32(buffer, LOCAL_HEADER_SIGNATURE);
+        put_u16(buffer, VERSION);
+        put_u16(buffer, flags(*size));
+        put_u16(buffer, 0);
+        put_u16(buffer, DOS_TIME);
+        put_u16(buffer, DOS_DATE);
+        put_u32(buffer, 0);
+        put_u32(buffer, *size);
+        put_u32(buffer, *size);
+        put_u16(buffer, name.len() as u16);
+        put_u16(buffer, 0);
+        buffer.extend_from_slice(name.as_bytes());
+    }
+
+    fn data_descriptor(&mut self, index: usize, crc: u32) {
+        let size = self.names[index].1;
+        put_u32(&mut self.buffer, DATA_DESCRIPTOR_SIGNATURE);
+        put_u32(&mut self.buffer, crc);
+        put_u32(&mut self.buffer, size);
+        put_u32(&mut self.buffer, size);
+    }
+
+    fn central_directory(&mut self) {
+        let start = self.position as u32;
+        let buffer = &mut self.buffer;
+        for ((name, size), packed) in self.names.iter().zip(&self.packed) {
+            put_u32(buffer, CENTRAL_HEADER_SIGNATURE);
+            put_u16(buffer, VERSION);
+            put_u16(buffer, VERSION);
+            put_u16(buffer, flags(*size));
+            put_u16(buffer, 0);
+            put_u16(buffer, DOS_TIME);
+            put_u16(buffer, DOS_DATE);
+            put_u32(buffer, packed.crc);
+            put_u32(buffer, *size);
+            put_u32(buffer, *size);
+            put_u16(buffer, name.len() as u16);
+            // Extra field, comment, disk, internal and external attributes
+            put_u16(buffer, 0);
+            put_u16(buffer, 0);
+            put_u16(buffer, 0);
+            put_u16(buffer, 0);
+            put_u32(buffer, 0);
+            put_u32(buffer, packed.offset);
+            buffer.extend_from_slice(name.as_bytes());
+        }
+        let directory_len = buffer.len() as u32;
+        put_u32(buffer, END_RECORD_SIGNATURE);
+        put_u16(buffer, 0);
+        put_u16(buffer, 0);
+        put_u16(buffer, self.names.len() as u16);
+        put_u16(buffer, self.names.len() as u16);
+        put_u32(buffer, directory_len);
+        put_u32(buffer, start);
+        put_u16(buffer, 0);
+    }
+}
+
+/// The general purpose flags of an entry of `size` bytes.
+fn flags(size: u32) -> u16 {
+    match size {
+        0 => FLAG_UTF8,
+        _ => FLAG_DATA_DESCRIPTOR | FLAG_UTF8,
+    }
+}
+
+fn put_u16(buffer: &mut Vec<u8>, value: u16) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for ZipStream<R> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        loop {
+            if this.buffer_offset < this.buffer.len() {
+                let len = buf.len().min(this.buffer.len() - this.buffer_offset);
+                buf[..len].copy_from_slice(&this.buffer[this.buffer_offset..this.buffer_offset + len]);
+                this.buffer_offset += len;
+                this.position += len as u64;
+                return Poll::Ready(Ok(len));
+            }
+            this.buffer.clear();
+            this.buffer_offset = 0;
+
+            match std::mem::replace(&mut this.state, PackState::Done) {
+                PackState::Header(index) if index == this.names.len() => {
+                    this.central_directory();
+                    this.state = PackState::Done;
+                }
+                PackState::Header(index) => {
+                    this.packed.push(Packed { offset: this.position as u32, crc: 0 });
+                    this.local_header(index);
+                    let reader = this.readers[index].take().expect("each entry is read once");
+                    this.state = PackState::Data(index, reader, flate2::Crc::new());
+                }
+                PackState::Data(index, mut reader, mut crc) => {
+                    let read = match Pin::new(&mut reader).poll_read(cx, buf) {
+                        Poll::Ready(read) => read,
+                        Poll::Pending => {
+                            this.state = PackState::Data(index, reader, crc);
+                            return Poll::Pending;
+                        }
+                    }?;
+                    let (name, size) = &this.names[index];
+                    let written = crc.amount() as u64 + read as u64;
+                    if written > *size as u64 || (read == 0 && written < *size as u64) {
+                        return Poll::Ready(Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("{} changed while it was being sent", name),
+                        )));
+                    }
+                    if read == 0 {
+                        this.packed[index].crc = crc.sum();
+                        if *size > 0 {
+                            this.data_descriptor(index, crc.sum());
+                        }
+                        this.state = PackState::Header(index + 1);
+                        continue;
+                    }
+                    crc.update(&buf[..read]);
+                    this.position += read as u64;
+                    this.state = PackState::Data(index, reader, crc);
+                    return Poll::Ready(Ok(read));
+                }
+                PackState::Done => return Poll::Ready(Ok(0)),
+            }
+        }
+    }
+}
+
+/// Where the entries of an archive made by `ZipStream` are, so that a
+/// position in it can be told as a position in an entry.
+#[derive(Clone, Debug)]
+pub(crate) struct Layout {
+    /// Name, start of the data and its length, in archive order.
+    entries: Vec<(String, u64, u64)>,
+    size: u64,
+}
+
+impl Layout {
+    /// `None` if the archive would be larger than a `u64` can count, which
+    /// only sizes made up by a peer get to.
+    pub(crate) fn new(entries: &[EntryMetadata]) -> Option<Self> {
+        let mut offset: u64 = 0;
+        let mut central = END_RECORD_LEN;
+        let entries = entries
+            .iter()
+            .map(|entry| {
+                let name_len = entry.name.len() as u64;
+                let start = offset.checked_add(LOCAL_HEADER_LEN + name_len)?;
+                let descriptor = if entry.size > 0 { DATA_DESCRIPTOR_LEN } else { 0 };
+                offset = start.checked_add(entry.size)?.checked_add(descriptor)?;
+                central = central.checked_add(CENTRAL_HEADER_LEN + name_len)?;
+                Some((entry.name.clone(), start, entry.size))
+            })
+            .collect::<Option<_>>()?;
+        Some(Layout { entries, size: offset.checked_add(central)? })
+    }
+
+    /// The size of the whole archive.
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The entry at `current` of `total` transferred bytes. If the transfer
+    /// is not the archive as packed, because it is compressed or was packed
+    /// differently, the position is scaled to the archive and only
+    /// approximate.
+    pub(crate) fn locate(&self, current: u64, total: u64) -> Option<EntryProgress> {
+        let position = if total == self.size || total == 0 {
+            current
+        } else {
+            (current as u128 * self.size as u128 / total as u128) as u64
+        };
+        let index = self.entries.iter().rposition(|(name, start, _)| start - name.len() as u64 - LOCAL_HEADER_LEN <= position)?;
+        let (name, start, len) = &self.entries[index];
+        Some(EntryProgress {
+            name: name.clone(),
+            index,
+            count: self.entries.len(),
+            current: if current >= total { *len } else { position.saturating_sub(*start).min(*len) },
+            total: *len,
+        })
+    }
+}
+
+/// Reads the file entries of the zip archive in `data`, all of them or those
+/// in `selection`, skipping directories.
+pub fn unpack(data: &[u8], selection: Option<&Selection>) -> ZipResult<Vec<ArchiveEntry>> {
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    let mut entries = Vec::with_capacity(archive.len());
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if file.is_dir() || selection.is_some_and(|selection| !selection.contains(file.name())) {
+            continue;
+        }
+
+        let mut data = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut data)?;
+        entries.push(ArchiveEntry {
+            name: file.name().to_owned(),
+            data,
+        });
+    }
+
+    Ok(entries)
+}
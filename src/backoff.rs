@@ -0,0 +1,37 @@
+//! Waiting after wrong codes before claiming another nameplate, so that
+//! the page cannot be scripted into guessing codes quickly.
+//!
+//! magic-wormhole already closes the mailbox with the `scary` mood when key
+//! confirmation fails. On top of that, every wrong code in a row doubles
+//! the time before the next claim, from one second up to a minute.
+
+use std::cell::Cell;
+
+const FIRST_DELAY_MS: f64 = 1_000.0;
+const MAX_DELAY_MS: f64 = 60_000.0;
+
+thread_local! {
+    static FAILURES: Cell<u32> = const { Cell::new(0) };
+    static BLOCKED_UNTIL: Cell<f64> = const { Cell::new(0.0) };
+}
+
+/// How many milliseconds are left until another claim is allowed, if any.
+pub(crate) fn remaining_ms() -> Option<f64> {
+    let remaining = BLOCKED_UNTIL.with(Cell::get) - js_sys::Date::now();
+    Some(remaining).filter(|&remaining| remaining > 0.0)
+}
+
+/// Records a wrong code and returns how many came in a row.
+pub(crate) fn failed() -> u32 {
+    let failures = FAILURES.with(|failures| {
+        failures.set(failures.get().saturating_add(1));
+        failures.get()
+    });
+    let delay = (FIRST_DELAY_MS * 2f64.powi(failures as i32 - 1)).min(MAX_DELAY_MS);
+    BLOCKED_UNTIL.with(|until| until.set(js_sys::Date::now() + delay));
+    failures
+}
+
+pub(crate) fn succeeded() {
+    FAILURES.with(|failures| failures.set(0));
+}
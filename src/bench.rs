@@ -0,0 +1,156 @@
+//! `ClientConfig.self_test`: a transfer of random data from this page to
+//! itself, through the configured rendezvous server and transit relay.
+
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use futures::channel::oneshot;
+use futures::io::AsyncRead;
+use magic_wormhole::Code;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::context::TransferContext;
+use crate::events::Events;
+use crate::js;
+use crate::metadata::FileMetadata;
+use crate::phase::Phase;
+use crate::ClientConfig;
+
+/// `size` bytes from xorshift64, which is fast and does not compress.
+struct RandomReader {
+    state: u64,
+    remaining: u64,
+}
+
+impl RandomReader {
+    fn new(size: u64) -> Result<Self, getrandom::Error> {
+        let mut seed = [0; 8];
+        getrandom::getrandom(&mut seed)?;
+        Ok(RandomReader {
+            // xorshift never leaves zero.
+            state: u64::from_le_bytes(seed) | 1,
+            remaining: size,
+        })
+    }
+}
+
+impl AsyncRead for RandomReader {
+    fn poll_read(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let len = buf.len().min(self.remaining as usize);
+        for chunk in buf[..len].chunks_mut(8) {
+            let mut x = self.state;
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            self.state = x;
+            chunk.copy_from_slice(&x.to_le_bytes()[..chunk.len()]);
+        }
+        self.remaining -= len as u64;
+        Poll::Ready(Ok(len))
+    }
+}
+
+/// The events of one side of the test: when each phase was reached, the
+/// last error, and for the sender, the code to hand to the receiver.
+struct Side {
+    start: f64,
+    phases: RefCell<Vec<(Phase, f64)>>,
+    error: RefCell<Option<String>>,
+    code: Cell<Option<oneshot::Sender<String>>>,
+}
+
+impl Side {
+    fn new(code: Option<oneshot::Sender<String>>) -> Rc<Self> {
+        Rc::new(Side {
+            start: js_sys::Date::now(),
+            phases: RefCell::new(Vec::new()),
+            error: RefCell::new(None),
+            code: Cell::new(code),
+        })
+    }
+
+    /// Milliseconds from the start of the test until `phase`.
+    fn reached(&self, phase: Phase) -> Option<f64> {
+        self.phases.borrow().iter().find(|(reached, _)| *reached == phase).map(|(_, at)| at - self.start)
+    }
+}
+
+impl Events for Side {
+    fn status(&self, _message: &str) {}
+
+    fn phase(&self, phase: Phase) {
+        self.phases.borrow_mut().push((phase, js_sys::Date::now()));
+    }
+
+    fn code(&self, code: &Code) {
+        if let Some(sender) = self.code.take() {
+            let _ = sender.send(code.to_string());
+        }
+    }
+
+    fn error(&self, message: &str) {
+        *self.error.borrow_mut() = Some(message.to_owned());
+    }
+}
+
+#[derive(Serialize)]
+struct Report {
+    bytes: u64,
+    elapsed_ms: f64,
+    average_bytes_per_second: f64,
+    peak_bytes_per_second: f64,
+    connection: String,
+    relay_url: String,
+    /// Until the rendezvous server allocated a code.
+    code_ms: Option<f64>,
+    /// Until both sides had confirmed the key.
+    key_exchange_ms: Option<f64>,
+    /// Until the receiver had its transit connection.
+    transit_ms: Option<f64>,
+}
+
+pub(crate) async fn run(config: &ClientConfig, size: u64) -> Result<JsValue, JsValue> {
+    let mut reader = RandomReader::new(size).map_err(|e| JsValue::from_str(&format!("Error generating random data: {}", e)))?;
+    let (code_sender, code) = oneshot::channel();
+    let sender = Side::new(Some(code_sender));
+    let receiver = Side::new(None);
+
+    let send_context = TransferContext::new(sender.clone(), None);
+    let metadata = FileMetadata::default();
+    let send = config.send_via_wormhole(&mut reader, size, "self-test.bin".to_owned(), &metadata, &send_context);
+    let receive = async {
+        let code = code.await.ok()?;
+        let context = TransferContext::new(receiver.clone(), None);
+        let received = config.receive_into(code, &context, |_, _| async { Ok(futures::io::sink()) }).await;
+        if received.is_none() {
+            // The sender would otherwise wait for a peer forever.
+            send_context.cancel.cancel();
+        }
+        received
+    };
+    let (sent, received) = futures::join!(send, receive);
+
+    let stats = match (sent, received) {
+        (Some(stats), Some(_)) => stats,
+        _ => {
+            let error = sender.error.borrow().clone().or_else(|| receiver.error.borrow().clone());
+            return Err(JsValue::from_str(&error.unwrap_or_else(|| "The self-test failed".to_owned())));
+        }
+    };
+    let report = Report {
+        bytes: size,
+        elapsed_ms: stats.elapsed_ms(),
+        average_bytes_per_second: stats.average_bytes_per_second(),
+        peak_bytes_per_second: stats.peak_bytes_per_second(),
+        connection: stats.connection(),
+        relay_url: stats.relay_url(),
+        code_ms: sender.reached(Phase::CodeAllocated),
+        key_exchange_ms: receiver.reached(Phase::KeyConfirmed),
+        transit_ms: receiver.reached(Phase::TransitConnected),
+    };
+    Ok(js::to_js(&report)?)
+}
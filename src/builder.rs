@@ -0,0 +1,176 @@
+//! Named ways of creating a `ClientConfig`: `ClientConfig.builder()` and
+//! `ClientConfig.from_js`, both starting from the public defaults below.
+
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{js, ClientConfig};
+
+/// The app id of the `wormhole` command line tool, for transfers with it.
+pub const DEFAULT_APPID: &str = "lothar.com/wormhole/text-or-file-xfer";
+
+/// The public rendezvous server of the Magic Wormhole project.
+pub const DEFAULT_RENDEZVOUS_URL: &str = "ws://relay.magic-wormhole.io:4000/v1";
+
+/// A public transit relay that accepts WebSockets.
+pub const DEFAULT_TRANSIT_SERVER_URL: &str = "ws://piegames.de:4002";
+
+/// How many words codes have, besides the nameplate.
+pub const DEFAULT_CODE_LENGTH: usize = 2;
+
+/// Builds a `ClientConfig`, see `ClientConfig.builder`. Each method returns
+/// the builder, so the calls chain; `build` checks the result.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct ClientConfigBuilder {
+    appid: String,
+    rendezvous_urls: Vec<String>,
+    transit_server_urls: Vec<String>,
+    code_length: usize,
+    require_tls: bool,
+}
+
+impl Default for ClientConfigBuilder {
+    fn default() -> Self {
+        ClientConfigBuilder {
+            appid: DEFAULT_APPID.to_owned(),
+            rendezvous_urls: Vec::new(),
+            transit_server_urls: Vec::new(),
+            code_length: DEFAULT_CODE_LENGTH,
+            require_tls: false,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl ClientConfigBuilder {
+    /// Defaults to `DEFAULT_APPID`.
+    pub fn appid(mut self, appid: String) -> ClientConfigBuilder {
+        self.appid = appid;
+        self
+    }
+
+    /// Adds a rendezvous server. Servers are tried in the order they were
+    /// added; without any, `DEFAULT_RENDEZVOUS_URL` is used.
+    pub fn rendezvous(mut self, url: String) -> ClientConfigBuilder {
+        self.rendezvous_urls.push(url);
+        self
+    }
+
+    /// Adds a transit relay; without any, `DEFAULT_TRANSIT_SERVER_URL` is
+    /// used.
+    pub fn transit(mut self, url: String) -> ClientConfigBuilder {
+        self.transit_server_urls.push(url);
+        self
+    }
+
+    /// How many words the codes we allocate have.
+    pub fn code_length(mut self, code_length: usize) -> ClientConfigBuilder {
+        self.code_length = code_length;
+        self
+    }
+
+    /// See `ClientConfig.set_require_tls`.
+    pub fn require_tls(mut self, require_tls: bool) -> ClientConfigBuilder {
+        self.require_tls = require_tls;
+        self
+    }
+
+    /// Fails like `ClientConfig.client_init`, if the code length is 0, or
+    /// with `require_tls` if a server is unencrypted (including the
+    /// defaults, which are).
+    pub fn build(self) -> Result<ClientConfig, JsValue> {
+        if self.code_length == 0 {
+            return Err(JsValue::from_str("Codes need at least one word"));
+        }
+        let or_default = |urls: Vec<String>, default: &str| if urls.is_empty() { vec![default.to_owned()] } else { urls };
+        let mut config = ClientConfig::new(
+            &self.appid,
+            or_default(self.rendezvous_urls, DEFAULT_RENDEZVOUS_URL),
+            or_default(self.transit_server_urls, DEFAULT_TRANSIT_SERVER_URL),
+            self.code_length,
+        )?;
+        config.set_require_tls(self.require_tls)?;
+        Ok(config)
+    }
+}
+
+/// One URL or several.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Urls {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl From<Urls> for Vec<String> {
+    fn from(urls: Urls) -> Self {
+        match urls {
+            Urls::One(url) => vec![url],
+            Urls::Many(urls) => urls,
+        }
+    }
+}
+
+/// The options object of `ClientConfig.from_js`, see `ClientOptions` in the
+/// TypeScript declarations.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub(crate) struct Options {
+    appid: Option<String>,
+    rendezvous: Option<Urls>,
+    transit: Option<Urls>,
+    code_length: Option<usize>,
+    require_tls: Option<bool>,
+}
+
+impl Options {
+    /// Reads the options object. It is converted as a whole first, as
+    /// serde-wasm-bindgen only looks up the fields a struct has and so would
+    /// let unknown options through.
+    pub(crate) fn from_js(options: JsValue) -> Result<Self, JsValue> {
+        js::from_js::<serde_json::Value>(options)
+            .map_err(|e| e.to_string())
+            .and_then(|options| serde_json::from_value(options).map_err(|e| e.to_string()))
+            .map_err(|e| JsValue::from_str(&format!("Invalid client options: {}", e)))
+    }
+
+    /// Replaces the settings of `config` that are given. `require_tls` is
+    /// turned off before and on after the URLs change, so that both can be
+    /// switched at once.
+    pub(crate) fn apply(self, config: &mut ClientConfig) -> Result<(), JsValue> {
+        let urls = |urls: Urls| Vec::from(urls).into_iter().map(JsValue::from).collect::<js_sys::Array>();
+        if self.require_tls == Some(false) {
+            config.set_require_tls(false)?;
+        }
+        if let Some(appid) = self.appid {
+            config.set_appid(appid)?;
+        }
+        if let Some(rendezvous) = self.rendezvous {
+            config.set_rendezvous_urls(urls(rendezvous))?;
+        }
+        if let Some(transit) = self.transit {
+            config.set_transit_server_urls(urls(transit))?;
+        }
+        if let Some(code_length) = self.code_length {
+            config.set_code_length(code_length)?;
+        }
+        if self.require_tls == Some(true) {
+            config.set_require_tls(true)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Options> for ClientConfigBuilder {
+    fn from(options: Options) -> Self {
+        let defaults = ClientConfigBuilder::default();
+        ClientConfigBuilder {
+            appid: options.appid.unwrap_or(defaults.appid),
+            rendezvous_urls: options.rendezvous.map(Vec::from).unwrap_or_default(),
+            transit_server_urls: options.transit.map(Vec::from).unwrap_or_default(),
+            code_length: options.code_length.unwrap_or(defaults.code_length),
+            require_tls: options.require_tls.unwrap_or(defaults.require_tls),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+#[derive(Default)]
+struct State {
+    cancelled: bool,
+    wakers: Vec<Waker>,
+}
+
+/// A shared flag for aborting a transfer from the outside.
+#[derive(Clone, Default)]
+pub(crate) struct Cancel(Rc<RefCell<State>>);
+
+impl Cancel {
+    pub(crate) fn cancel(&self) {
+        let wakers = {
+            let mut state = self.0.borrow_mut();
+            state.cancelled = true;
+            std::mem::take(&mut state.wakers)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.0.borrow().cancelled
+    }
+
+    /// A future that completes once `cancel` has been called.
+    pub(crate) fn cancelled(&self) -> Cancelled {
+        Cancelled(self.clone())
+    }
+}
+
+pub(crate) struct Cancelled(Cancel);
+
+impl Future for Cancelled {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = (self.0).0.borrow_mut();
+        if state.cancelled {
+            return Poll::Ready(());
+        }
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
@@ -0,0 +1,37 @@
+//! Getting what is on screen into a `Blob` for sending: the clipboard's
+//! text and the content of a canvas.
+
+use js_sys::Promise;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+/// The text on the clipboard, as a `text/plain` blob. Browsers only allow
+/// this in a page with focus, and may ask the user first.
+pub(crate) async fn clipboard_text() -> Result<web_sys::Blob, JsValue> {
+    let navigator = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("navigator"))?;
+    let clipboard = js_sys::Reflect::get(&navigator, &JsValue::from_str("clipboard"))?;
+    if clipboard.is_undefined() {
+        return Err(JsValue::from_str("The clipboard is not available here"));
+    }
+    let read_text: js_sys::Function = js_sys::Reflect::get(&clipboard, &JsValue::from_str("readText"))?.dyn_into()?;
+    let text = JsFuture::from(read_text.call0(&clipboard)?.unchecked_into::<Promise>()).await?;
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("text/plain;charset=utf-8");
+    web_sys::Blob::new_with_str_sequence_and_options(&js_sys::Array::of1(&text), &options)
+}
+
+/// The content of `canvas` encoded as `mime_type`, or PNG if the browser
+/// does not support that type.
+pub(crate) async fn canvas_blob(canvas: &web_sys::HtmlCanvasElement, mime_type: &str) -> Result<web_sys::Blob, JsValue> {
+    let mut to_blob = Err(JsValue::UNDEFINED);
+    let encoded = Promise::new(&mut |resolve, _reject| {
+        to_blob = canvas.to_blob_with_type(&resolve, mime_type);
+    });
+    to_blob?;
+    let blob = JsFuture::from(encoded).await?;
+    if blob.is_null() {
+        return Err(JsValue::from_str("The canvas is empty"));
+    }
+    Ok(blob.unchecked_into())
+}
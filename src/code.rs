@@ -0,0 +1,75 @@
+use magic_wormhole::Code;
+use wasm_bindgen::prelude::*;
+
+/// A wormhole code split into its parts, e.g. `4-hurricane-equipment` into
+/// the nameplate `4` and the words `hurricane` and `equipment`.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WormholeCode {
+    nameplate: String,
+    words: Vec<String>,
+}
+
+impl From<&Code> for WormholeCode {
+    fn from(code: &Code) -> Self {
+        let code = code.to_string();
+        let mut parts = code.split('-').map(str::to_owned);
+        WormholeCode {
+            nameplate: parts.next().unwrap_or_default(),
+            words: parts.collect(),
+        }
+    }
+}
+
+/// Lowercase RFC 4648 base32, which survives being typed or read aloud.
+const BASE32: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// Random characters in a high-entropy code, 5 bits each.
+const RANDOM_CHARS: usize = 26;
+
+/// A code with a random seven digit nameplate, above the ones servers
+/// allocate, and a single word of 130 random bits, instead of words from
+/// the word list. Such a code is too long to type and meant to be shared
+/// as a link or QR code, but cannot be guessed in the one try a peer gets.
+pub(crate) fn random() -> Result<Code, getrandom::Error> {
+    let mut bytes = [0; 4 + RANDOM_CHARS];
+    getrandom::getrandom(&mut bytes)?;
+    let nameplate = 1_000_000 + u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) % 9_000_000;
+    let password: String = bytes[4..].iter().map(|&byte| BASE32[(byte & 31) as usize] as char).collect();
+    Ok(Code(format!("{}-{}", nameplate, password)))
+}
+
+#[wasm_bindgen]
+impl WormholeCode {
+    /// Splits a code as typed by the user. The nameplate has to be a number
+    /// and at least one word has to follow it.
+    pub fn parse(code: &str) -> Result<WormholeCode, JsValue> {
+        let code = WormholeCode::from(&Code(code.trim().to_owned()));
+        if code.nameplate.is_empty() || !code.nameplate.chars().all(|c| c.is_ascii_digit()) {
+            return Err(JsValue::from_str("The code has to start with a number"));
+        }
+        if code.words.iter().all(String::is_empty) {
+            return Err(JsValue::from_str("The code has no words"));
+        }
+        Ok(code)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn nameplate(&self) -> String {
+        self.nameplate.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn words(&self) -> js_sys::Array {
+        self.words.iter().map(|word| JsValue::from_str(word)).collect()
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_code_string(&self) -> String {
+        std::iter::once(&self.nameplate)
+            .chain(&self.words)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+}
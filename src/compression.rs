@@ -0,0 +1,60 @@
+//! Optional gzip compression of the transferred file.
+//!
+//! Peers that enable it announce `{"magic-wormhole-wasm": {"compression":
+//! ["gzip"]}}` in their app versions. The file is compressed only when both
+//! sides announce it, so other clients always get it as it is.
+
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::APP_VERSION_KEY;
+
+const GZIP: &str = "gzip";
+
+/// Adds the compression announcement to our app versions.
+pub(crate) fn announce(app_versions: &mut serde_json::Value) {
+    if let Some(fields) = crate::own_app_version(app_versions) {
+        fields.insert("compression".to_owned(), serde_json::json!([GZIP]));
+    }
+}
+
+/// Whether the peer announced that it understands gzip compression.
+pub(crate) fn negotiated(peer_version: &serde_json::Value) -> bool {
+    peer_version
+        .pointer(&format!("/{}/compression", APP_VERSION_KEY))
+        .and_then(serde_json::Value::as_array)
+        .is_some_and(|methods| methods.iter().any(|method| method == GZIP))
+}
+
+pub(crate) fn compress(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// What the start of gzip compressed data decompresses to, at most
+/// `max_size` bytes. Stops quietly where `data` ends.
+pub(crate) fn decompress_prefix(data: &[u8], max_size: usize) -> Vec<u8> {
+    let mut decompressed = Vec::new();
+    // On errors, what was decompressed until then is kept
+    let _ = GzDecoder::new(data).take(max_size as u64).read_to_end(&mut decompressed);
+    decompressed
+}
+
+/// Fails if the data decompresses to more than `max_size` bytes, which is
+/// checked while decompressing.
+pub(crate) fn decompress(data: &[u8], max_size: Option<u64>) -> std::io::Result<Vec<u8>> {
+    let limit = max_size.unwrap_or(u64::MAX);
+    let mut decompressed = Vec::new();
+    GzDecoder::new(data).take(limit.saturating_add(1)).read_to_end(&mut decompressed)?;
+    if decompressed.len() as u64 > limit {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("the file is larger than the limit of {} bytes", limit),
+        ));
+    }
+    Ok(decompressed)
+}
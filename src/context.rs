@@ -0,0 +1,49 @@
+use std::rc::Rc;
+
+use wasm_bindgen::JsCast;
+
+use crate::cancel::Cancel;
+use crate::events::Events;
+use crate::pause::Pause;
+use crate::VerifierCallback;
+
+/// The page element the JS entry points write their status messages to.
+impl Events for web_sys::HtmlElement {
+    fn status(&self, message: &str) {
+        self.set_inner_text(message);
+    }
+}
+
+/// Everything a single transfer reports to and is controlled by.
+pub(crate) struct TransferContext {
+    pub(crate) events: Rc<dyn Events>,
+    /// Gates the transfer on the session verifier, see `verifier::confirm`.
+    pub(crate) on_verifier: Option<js_sys::Function>,
+    pub(crate) cancel: Cancel,
+    pub(crate) pause: Pause,
+    /// The SHA-256 the received data has to match, as hex.
+    pub(crate) expected_sha256: Option<String>,
+}
+
+impl TransferContext {
+    pub(crate) fn new(events: Rc<dyn Events>, on_verifier: Option<VerifierCallback>) -> Self {
+        TransferContext {
+            events,
+            on_verifier: on_verifier.map(JsCast::unchecked_into),
+            cancel: Cancel::default(),
+            pause: Pause::default(),
+            expected_sha256: None,
+        }
+    }
+
+    /// The same transfer, reporting its events to `events` instead.
+    pub(crate) fn with_events(&self, events: Rc<dyn Events>) -> Self {
+        TransferContext {
+            events,
+            on_verifier: self.on_verifier.clone(),
+            cancel: self.cancel.clone(),
+            pause: self.pause.clone(),
+            expected_sha256: self.expected_sha256.clone(),
+        }
+    }
+}
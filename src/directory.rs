@@ -0,0 +1,127 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+#[wasm_bindgen]
+extern {
+    /// An entry of a directory from the File System Access API.
+    #[derive(Clone)]
+    pub type FileSystemHandle;
+
+    #[wasm_bindgen(method, getter)]
+    fn kind(this: &FileSystemHandle) -> String;
+
+    #[wasm_bindgen(method, getter)]
+    pub fn name(this: &FileSystemHandle) -> String;
+
+    #[wasm_bindgen(extends = FileSystemHandle)]
+    #[derive(Clone)]
+    pub type FileSystemFileHandle;
+
+    #[wasm_bindgen(method, js_name = getFile)]
+    pub(crate) fn get_file(this: &FileSystemFileHandle) -> js_sys::Promise;
+
+    #[wasm_bindgen(method, js_name = createWritable)]
+    pub(crate) fn create_writable(this: &FileSystemFileHandle) -> js_sys::Promise;
+
+    #[wasm_bindgen(method, js_name = createSyncAccessHandle)]
+    pub(crate) fn create_sync_access_handle(this: &FileSystemFileHandle) -> js_sys::Promise;
+
+    /// A directory picked with `showDirectoryPicker()` or dropped onto the page.
+    #[wasm_bindgen(extends = FileSystemHandle)]
+    #[derive(Clone)]
+    pub type FileSystemDirectoryHandle;
+
+    #[wasm_bindgen(method)]
+    fn values(this: &FileSystemDirectoryHandle) -> js_sys::AsyncIterator;
+
+    #[wasm_bindgen(method, js_name = getDirectoryHandle)]
+    fn get_directory_handle(this: &FileSystemDirectoryHandle, name: &str, options: &JsValue) -> js_sys::Promise;
+
+    #[wasm_bindgen(method, js_name = getFileHandle)]
+    pub(crate) fn get_file_handle(this: &FileSystemDirectoryHandle, name: &str, options: &JsValue) -> js_sys::Promise;
+}
+
+/// Collects every file below `directory` into `entries`, named by their
+/// path relative to `directory` with `/` as separator. Only the `File`s are
+/// looked up, which reads their size but not their content.
+pub(crate) fn read_directory<'a>(
+    directory: &'a FileSystemDirectoryHandle,
+    prefix: String,
+    entries: &'a mut Vec<(String, web_sys::File)>,
+) -> Pin<Box<dyn Future<Output = Result<(), JsValue>> + 'a>> {
+    Box::pin(async move {
+        let iterator = directory.values();
+        loop {
+            let next: js_sys::IteratorNext = JsFuture::from(iterator.next()?).await?.unchecked_into();
+            if next.done() {
+                return Ok(());
+            }
+
+            let handle: FileSystemHandle = next.value().unchecked_into();
+            let path = format!("{}{}", prefix, handle.name());
+            match handle.kind().as_str() {
+                "file" => {
+                    let file: web_sys::File = JsFuture::from(handle.unchecked_ref::<FileSystemFileHandle>().get_file())
+                        .await?
+                        .unchecked_into();
+                    entries.push((path, file));
+                }
+                "directory" => {
+                    read_directory(handle.unchecked_ref(), format!("{}/", path), entries).await?;
+                }
+                _ => {}
+            }
+        }
+    })
+}
+
+/// `{create: true}`, for opening handles that may not exist yet.
+pub(crate) fn create_options() -> Result<JsValue, JsValue> {
+    let options = js_sys::Object::new();
+    js_sys::Reflect::set(&options, &JsValue::from_str("create"), &JsValue::TRUE)?;
+    Ok(options.into())
+}
+
+/// Creates the file at `path` below `directory`, with the subdirectories
+/// on the way. `None` if `path` names no file or leaves `directory`.
+pub(crate) async fn create_file(directory: &FileSystemDirectoryHandle, path: &str) -> Result<Option<FileSystemFileHandle>, JsValue> {
+    let options = create_options()?;
+    let mut components: Vec<&str> = path
+        .split('/')
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect();
+    if components.contains(&"..") {
+        return Ok(None);
+    }
+    let file_name = match components.pop() {
+        Some(file_name) => file_name,
+        None => return Ok(None),
+    };
+
+    let mut parent = directory.clone();
+    for component in components {
+        parent = JsFuture::from(parent.get_directory_handle(component, &options))
+            .await?
+            .unchecked_into();
+    }
+
+    let file = JsFuture::from(parent.get_file_handle(file_name, &options)).await?;
+    Ok(Some(file.unchecked_into()))
+}
+
+/// Calls the `on_progress` of `ClientConfig.receive_directory` for the
+/// entry `name`. `count` is left out where it is not known.
+pub(crate) fn report_written(on_progress: Option<&js_sys::Function>, name: &str, index: usize, count: Option<usize>) {
+    if let Some(callback) = on_progress {
+        let _ = callback.call3(
+            &JsValue::NULL,
+            &JsValue::from_str(name),
+            &JsValue::from(index as u32),
+            &count.map_or(JsValue::UNDEFINED, |count| JsValue::from(count as u32)),
+        );
+    }
+}
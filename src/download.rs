@@ -0,0 +1,28 @@
+//! Streaming a received file to the browser's download manager through a
+//! service worker, see `ClientConfig.receive_stream`.
+//!
+//! The page hands the stream it gets to its service worker, which answers
+//! a request for a download URL with it:
+//!
+//! 1. The page posts `{type: "wormhole-download", id: string, filename:
+//!    string, filesize: bigint, stream: ReadableStream}` to
+//!    `navigator.serviceWorker.controller`, with `stream` in the transfer
+//!    list. `id` is any string unique to this download.
+//! 2. The service worker keeps the message until it receives a fetch of
+//!    `wormhole-download/<id>` below its scope, and responds to that with
+//!    `new Response(stream)`, with `Content-Disposition: attachment` and
+//!    `Content-Length` headers set from `filename` and `filesize`.
+//! 3. The page starts the download by loading that URL, e.g. in a hidden
+//!    iframe, so that the page itself stays open.
+//!
+//! `www/download-sw.js` implements the service worker side.
+
+use crate::stream::{self, StreamWriter};
+use crate::ReadableStream;
+
+/// A pipe whose readable end is handed to JS, and whose writable end the
+/// received data goes to.
+pub(crate) fn pipe() -> (ReadableStream, StreamWriter) {
+    let (readable, writable) = stream::pipe();
+    (readable, StreamWriter::new(&writable))
+}
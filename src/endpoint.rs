@@ -0,0 +1,102 @@
+//! Checks on the rendezvous and transit server URLs, done when they are
+//! configured so that a malformed URL, or one the browser would refuse,
+//! fails with an error that says why, instead of a generic one (or a panic
+//! in the rendezvous client) once a transfer connects.
+
+use url::Url;
+use wasm_bindgen::prelude::*;
+
+/// Which server a URL is for.
+#[derive(Clone, Copy)]
+pub(crate) enum Server {
+    Rendezvous,
+    Transit,
+}
+
+impl Server {
+    fn name(self) -> &'static str {
+        match self {
+            Server::Rendezvous => "rendezvous",
+            Server::Transit => "transit server",
+        }
+    }
+
+    fn schemes(self) -> &'static [&'static str] {
+        match self {
+            Server::Rendezvous => &["ws", "wss"],
+            Server::Transit => &["ws", "wss", "tcp"],
+        }
+    }
+}
+
+/// Whether the page, or the worker's script, was loaded over HTTPS. Such
+/// pages may only open `wss://` WebSockets; browsers block `ws://` ones as
+/// mixed content.
+fn page_is_secure() -> bool {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("location"))
+        .and_then(|location| js_sys::Reflect::get(&location, &JsValue::from_str("protocol")))
+        .is_ok_and(|protocol| protocol.as_string().as_deref() == Some("https:"))
+}
+
+fn scheme(url: &str) -> String {
+    url.split(':').next().unwrap_or_default().to_ascii_lowercase()
+}
+
+/// `url` with `http` and `https` replaced by `ws` and `wss`, which reach the
+/// same server, and surrounding whitespace removed.
+fn normalize(url: &str) -> String {
+    let url = url.trim();
+    match scheme(url).as_str() {
+        "http" => format!("ws{}", &url[4..]),
+        "https" => format!("wss{}", &url[5..]),
+        _ => url.to_owned(),
+    }
+}
+
+/// Why `url` is not a URL of `server` at all: it must have one of its
+/// schemes and a host, and `tcp` a port and no path.
+fn malformed(url: &str, server: Server) -> Option<String> {
+    let parsed = match Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(e) => return Some(e.to_string()),
+    };
+    if !server.schemes().contains(&parsed.scheme()) {
+        return Some(format!("the scheme must be one of {}", server.schemes().join(", ")));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Some("it has no host".to_owned());
+    }
+    if parsed.scheme() == "tcp" {
+        if parsed.port().is_none() {
+            return Some("it has no port".to_owned());
+        }
+        if !matches!(parsed.path(), "" | "/") {
+            return Some("tcp URLs have no path".to_owned());
+        }
+    }
+    None
+}
+
+/// The normalized `url`, or why it cannot be used for `server`. With
+/// `require_tls`, the plaintext `ws` and `tcp` schemes are refused.
+pub(crate) fn validate(url: &str, server: Server, require_tls: bool) -> Result<String, String> {
+    let url = normalize(url);
+    if let Some(reason) = malformed(&url, server) {
+        return Err(format!("Invalid {} url {}: {}", server.name(), url, reason));
+    }
+    match scheme(&url).as_str() {
+        "ws" | "tcp" if require_tls => Err(format!("{} is not encrypted, which require_tls forbids", url)),
+        "ws" if page_is_secure() => Err(format!(
+            "{} cannot be used from a page served over HTTPS: browsers block ws:// WebSockets there as mixed content, use wss:// instead",
+            url
+        )),
+        _ => Ok(url),
+    }
+}
+
+/// Validates all of `urls`, see `validate`.
+pub(crate) fn validate_all(urls: &[String], server: Server, require_tls: bool) -> Result<Vec<String>, JsValue> {
+    urls.iter()
+        .map(|url| validate(url, server, require_tls).map_err(|e| JsValue::from_str(&e)))
+        .collect()
+}
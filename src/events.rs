@@ -0,0 +1,99 @@
+use magic_wormhole::{Code, Wormhole};
+
+use crate::phase::Phase;
+use crate::stats::Progress;
+use crate::timeout::Timeout;
+use crate::trace::Direction;
+use crate::TransitInfo;
+
+/// Receives everything a transfer reports while it runs. The DOM based
+/// methods write it into an element, workers post it to the page, and Rust
+/// callers of `api` implement it themselves. Only `status` is required.
+pub trait Events {
+    /// A human-readable description of what the transfer is doing.
+    fn status(&self, message: &str);
+
+    /// The transfer moved on to `phase`.
+    fn phase(&self, _phase: Phase) {}
+
+    /// The message of the day sent by the rendezvous server.
+    fn welcome(&self, message: &str) {
+        console_log!("Rendezvous server: {}", message);
+    }
+
+    /// The code the peer has to enter, once it has been allocated.
+    fn code(&self, code: &Code) {
+        self.status(&format!("wormhole code:  {}", code));
+    }
+
+    /// A second code for the same file, see
+    /// `ClientConfig.set_alternate_code_length`. Whichever code the peer
+    /// uses first wins.
+    fn alternate_code(&self, code: &Code) {
+        console_log!("Alternate wormhole code: {}", code);
+    }
+
+    /// The code expires in `remaining_ms`, see `ClientConfig.set_code_expiry`.
+    fn code_countdown(&self, _remaining_ms: f64) {}
+
+    /// Nobody used the code before it expired.
+    fn code_expired(&self) {
+        self.error("The code expired");
+    }
+
+    /// The peer has connected and the session is confirmed. The wormhole
+    /// carries the app versions the peer sent and the session key.
+    fn connected(&self, _wormhole: &Wormhole) {
+        self.status("connected");
+    }
+
+    /// The transit connection to the peer is open.
+    fn transit(&self, info: TransitInfo) {
+        self.status(&format!("connected ({})", info.kind()));
+    }
+
+    /// No data moved for `idle_ms` while the transfer runs, see
+    /// `ClientConfig.set_stall_warning`. The transfer goes on.
+    fn stalled(&self, idle_ms: f64) {
+        self.status(&format!("stalled for {:.0} s", idle_ms / 1000.0));
+    }
+
+    /// The browser went offline, which holds the transfer, or back
+    /// `online`. See the `network` module for what survives that.
+    fn network(&self, online: bool) {
+        self.status(if online { "back online" } else { "offline, waiting for the network" });
+    }
+
+    /// Key confirmation failed: the code was mistyped, or someone tried to
+    /// guess it. magic-wormhole has already closed the mailbox as "scary",
+    /// and the next code can only be tried after a delay that grows with
+    /// every wrong code in a row.
+    fn wrong_code(&self) {
+        self.error("Wrong code, check it and try again");
+    }
+
+    /// The transfer was given up because `timeout` expired.
+    fn timed_out(&self, timeout: Timeout) {
+        self.error(timeout.message());
+    }
+
+    /// The peer aborted the transfer with `message`, typically because it
+    /// rejected the offer.
+    fn rejected(&self, message: &str) {
+        self.error(&format!("The peer rejected the transfer: {}", message));
+    }
+
+    /// Data moved; reported as often as `ClientConfig.set_progress_interval`
+    /// allows, and always once all of it did.
+    fn progress(&self, progress: Progress) {
+        console_log!("Progress: {}/{} ({:.0} B/s)", progress.current, progress.total, progress.smoothed_bytes_per_second);
+    }
+
+    fn error(&self, message: &str) {
+        console_log!("{}", message);
+        self.status(message);
+    }
+
+    /// A protocol message or other detail worth tracing, see `trace`.
+    fn trace(&self, _direction: Direction, _event: &str, _fields: serde_json::Value) {}
+}
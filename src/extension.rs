@@ -0,0 +1,72 @@
+//! Running transfers in the background service worker of a Manifest V3
+//! browser extension.
+//!
+//! Such a worker has no DOM, so only `WormholeSession` is usable there, and
+//! the browser stops it after about 30 seconds without extension events,
+//! even with a WebSocket open that is merely waiting for the peer. While a
+//! session runs, `KeepAlive` calls an extension API every 20 seconds, which
+//! resets that timer.
+//!
+//! A stopped worker cannot pick its transfers up again: transfer-v1 has no
+//! way to resume, and the code is used up once the peer connected. To tell
+//! the user what happened, save `WormholeSession.state` to
+//! `chrome.storage.session` on its `phase` and `progress` events, and read
+//! it back when the worker starts.
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::runtime::Runtime;
+
+#[wasm_bindgen]
+extern {
+    #[wasm_bindgen(js_name = setInterval)]
+    fn set_interval(handler: &js_sys::Function, timeout: i32) -> JsValue;
+
+    #[wasm_bindgen(js_name = clearInterval)]
+    fn clear_interval(id: &JsValue);
+}
+
+const KEEPALIVE_INTERVAL_MS: i32 = 20_000;
+
+/// `chrome.runtime` in an extension's service worker.
+fn extension_runtime() -> Option<JsValue> {
+    if Runtime::detect() != Runtime::Worker {
+        return None;
+    }
+    let chrome = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("chrome")).ok()?;
+    let runtime = js_sys::Reflect::get(&chrome, &JsValue::from_str("runtime")).ok()?;
+    let id = js_sys::Reflect::get(&runtime, &JsValue::from_str("id")).ok()?;
+    Some(runtime).filter(|_| id.is_string())
+}
+
+/// Keeps an extension's service worker alive until it is dropped. Does
+/// nothing anywhere else.
+pub(crate) struct KeepAlive {
+    interval: JsValue,
+    _closure: Closure<dyn FnMut()>,
+}
+
+impl KeepAlive {
+    pub(crate) fn start() -> Option<Self> {
+        let runtime = extension_runtime()?;
+        let get_platform_info: js_sys::Function = js_sys::Reflect::get(&runtime, &JsValue::from_str("getPlatformInfo"))
+            .ok()?
+            .dyn_into()
+            .ok()?;
+        let closure = Closure::wrap(Box::new(move || {
+            let _ = get_platform_info.call0(&runtime);
+        }) as Box<dyn FnMut()>);
+        let interval = set_interval(closure.as_ref().unchecked_ref(), KEEPALIVE_INTERVAL_MS);
+        Some(KeepAlive {
+            interval,
+            _closure: closure,
+        })
+    }
+}
+
+impl Drop for KeepAlive {
+    fn drop(&mut self) {
+        clear_interval(&self.interval);
+    }
+}
@@ -0,0 +1,351 @@
+//! Extracting single entries from a zip archive kept in a `Blob`, like one
+//! received with `receive_to_opfs` or `receive_to_indexed_db`, without
+//! reading the rest of it: `ArchiveIndex.read` parses only the central
+//! directory at the end of the archive, and entries are cut out of the
+//! `Blob` as they are extracted.
+
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use flate2::write::DeflateDecoder;
+use futures::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::archive::{
+    CENTRAL_HEADER_LEN, CENTRAL_HEADER_SIGNATURE, DEFLATED, END_RECORD_LEN, END_RECORD_SIGNATURE,
+    LOCAL_HEADER_LEN, LOCAL_HEADER_SIGNATURE, MAX_COMMENT_LEN, STORED, ZIP64_END_RECORD_LEN, ZIP64_END_RECORD_SIGNATURE,
+    ZIP64_EXTRA_ID, ZIP64_LOCATOR_LEN, ZIP64_LOCATOR_SIGNATURE,
+};
+use crate::directory::{self, FileSystemDirectoryHandle};
+use crate::js;
+use crate::mime;
+use crate::offer::Selection;
+use crate::stream::{StreamReader, StreamWriter, WritableStream};
+use crate::typescript::OfferEntries;
+
+/// An entry as the central directory describes it.
+#[derive(Clone, Debug)]
+struct IndexEntry {
+    name: String,
+    method: u16,
+    compressed_size: u64,
+    size: u64,
+    header_offset: u64,
+}
+
+impl IndexEntry {
+    fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+}
+
+/// An entry of `ArchiveIndex.entries`, shaped like those of `Offer.entries`.
+#[derive(Serialize)]
+struct ListedEntry<'a> {
+    name: &'a str,
+    size: u64,
+    kind: &'static str,
+}
+
+pub(crate) fn malformed(reason: &str) -> JsValue {
+    JsValue::from_str(&format!("Not a readable zip archive: {}", reason))
+}
+
+pub(crate) fn u16_at(data: &[u8], at: usize) -> Result<u16, JsValue> {
+    data.get(at..at + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| malformed("a record is cut off"))
+}
+
+pub(crate) fn u32_at(data: &[u8], at: usize) -> Result<u32, JsValue> {
+    Ok(u16_at(data, at)? as u32 | (u16_at(data, at + 2)? as u32) << 16)
+}
+
+pub(crate) fn u64_at(data: &[u8], at: usize) -> Result<u64, JsValue> {
+    Ok(u32_at(data, at)? as u64 | (u32_at(data, at + 4)? as u64) << 32)
+}
+
+/// How much of an entry is read at once.
+pub(crate) const CHUNK_LEN: usize = 64 * 1024;
+
+/// The bytes from `start` to `end` of `blob`.
+async fn read_range(blob: &web_sys::Blob, start: u64, end: u64) -> Result<Vec<u8>, JsValue> {
+    let slice = blob.slice_with_f64_and_f64(start as f64, end as f64)?;
+    let buffer = JsFuture::from(slice.array_buffer()).await?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+/// Where the central directory is and how many entries it has, from the
+/// end of central directory record, or its zip64 version if the archive
+/// needs one.
+async fn locate_directory(blob: &web_sys::Blob) -> Result<(u64, u64, u64), JsValue> {
+    let size = blob.size() as u64;
+    let tail_start = size.saturating_sub(END_RECORD_LEN + MAX_COMMENT_LEN + ZIP64_LOCATOR_LEN);
+    let tail = read_range(blob, tail_start, size).await?;
+    let last = tail.len().checked_sub(END_RECORD_LEN as usize).ok_or_else(|| malformed("it is too short"))?;
+    let end = (0..=last)
+        .rev()
+        .find(|&at| u32_at(&tail, at).ok() == Some(END_RECORD_SIGNATURE))
+        .ok_or_else(|| malformed("there is no end of central directory record"))?;
+
+    let count = u16_at(&tail, end + 10)? as u64;
+    let directory_size = u32_at(&tail, end + 12)? as u64;
+    let directory_offset = u32_at(&tail, end + 16)? as u64;
+    if count != 0xffff && directory_size != 0xffff_ffff && directory_offset != 0xffff_ffff {
+        return Ok((directory_offset, directory_size, count));
+    }
+
+    let locator = end
+        .checked_sub(ZIP64_LOCATOR_LEN as usize)
+        .filter(|&at| u32_at(&tail, at).ok() == Some(ZIP64_LOCATOR_SIGNATURE))
+        .ok_or_else(|| malformed("the zip64 end of central directory locator is missing"))?;
+    let record_offset = u64_at(&tail, locator + 8)?;
+    let record = read_range(blob, record_offset, record_offset + ZIP64_END_RECORD_LEN).await?;
+    if u32_at(&record, 0)? != ZIP64_END_RECORD_SIGNATURE {
+        return Err(malformed("the zip64 end of central directory record is missing"));
+    }
+    Ok((u64_at(&record, 48)?, u64_at(&record, 40)?, u64_at(&record, 32)?))
+}
+
+/// The entries of the central directory in `directory`.
+fn parse_directory(directory: &[u8], count: u64) -> Result<Vec<IndexEntry>, JsValue> {
+    let mut entries = Vec::new();
+    let mut at = 0;
+    for _ in 0..count {
+        if u32_at(directory, at)? != CENTRAL_HEADER_SIGNATURE {
+            return Err(malformed("a central directory header is broken"));
+        }
+        let method = u16_at(directory, at + 10)?;
+        let mut compressed_size = u32_at(directory, at + 20)? as u64;
+        let mut size = u32_at(directory, at + 24)? as u64;
+        let name_len = u16_at(directory, at + 28)? as usize;
+        let extra_len = u16_at(directory, at + 30)? as usize;
+        let comment_len = u16_at(directory, at + 32)? as usize;
+        let mut header_offset = u32_at(directory, at + 42)? as u64;
+
+        let name_start = at + CENTRAL_HEADER_LEN as usize;
+        let extra_start = name_start + name_len;
+        let name = directory.get(name_start..extra_start).ok_or_else(|| malformed("a file name is cut off"))?;
+        let extra = directory.get(extra_start..extra_start + extra_len).ok_or_else(|| malformed("an extra field is cut off"))?;
+
+        // The zip64 field holds the values that did not fit, in this order
+        let mut field = 0;
+        while field + 4 <= extra.len() {
+            let id = u16_at(extra, field)?;
+            let len = u16_at(extra, field + 2)? as usize;
+            if id == ZIP64_EXTRA_ID {
+                let mut value = field + 4;
+                for target in [&mut size, &mut compressed_size, &mut header_offset].iter_mut() {
+                    if **target == 0xffff_ffff {
+                        **target = u64_at(extra, value)?;
+                        value += 8;
+                    }
+                }
+            }
+            field += 4 + len;
+        }
+
+        entries.push(IndexEntry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            method,
+            compressed_size,
+            size,
+            header_offset,
+        });
+        at = extra_start + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+/// The central directory of a zip archive in a `Blob`, to list it and
+/// extract entries of it, which are read from the `Blob` only then.
+///
+/// Entries may be stored or deflated. Their checksums are not verified;
+/// for a received archive, the transfer already was.
+#[wasm_bindgen]
+pub struct ArchiveIndex {
+    blob: web_sys::Blob,
+    entries: Vec<IndexEntry>,
+}
+
+impl ArchiveIndex {
+    fn entry(&self, name: &str) -> Result<&IndexEntry, JsValue> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| JsValue::from_str(&format!("The archive has no entry {}", name)))
+    }
+
+    /// The data of `entry` as it is stored in the archive.
+    async fn raw(&self, entry: &IndexEntry) -> Result<web_sys::Blob, JsValue> {
+        let header = read_range(&self.blob, entry.header_offset, entry.header_offset + LOCAL_HEADER_LEN).await?;
+        if u32_at(&header, 0)? != LOCAL_HEADER_SIGNATURE {
+            return Err(malformed(&format!("the local header of {} is broken", entry.name)));
+        }
+        let start = entry.header_offset + LOCAL_HEADER_LEN + u16_at(&header, 26)? as u64 + u16_at(&header, 28)? as u64;
+        let end = start + entry.compressed_size;
+        if end > self.blob.size() as u64 {
+            return Err(malformed(&format!("{} extends past the end", entry.name)));
+        }
+        self.blob.slice_with_f64_and_f64_and_content_type(start as f64, end as f64, mime::from_filename(&entry.name))
+    }
+
+    /// `entry` as a `Blob`. A stored entry is the slice of the archive,
+    /// a deflated one is inflated a chunk at a time into the parts of a new
+    /// `Blob`.
+    async fn extract_entry(&self, entry: &IndexEntry) -> Result<web_sys::Blob, JsValue> {
+        let raw = self.raw(entry).await?;
+        match entry.method {
+            STORED => Ok(raw),
+            DEFLATED => {
+                let mut parts = BlobParts(js_sys::Array::new());
+                inflate(&raw, entry, &mut parts).await?;
+                let options = web_sys::BlobPropertyBag::new();
+                options.set_type(mime::from_filename(&entry.name));
+                web_sys::Blob::new_with_u8_array_sequence_and_options(&parts.0, &options)
+            }
+            method => Err(unsupported(entry, method)),
+        }
+    }
+
+    /// Writes `entry` to `writer` a chunk at a time, inflating it if it is
+    /// deflated, and closes it.
+    async fn write_entry<W: AsyncWrite + Unpin>(&self, entry: &IndexEntry, writer: &mut W) -> Result<(), JsValue> {
+        let raw = self.raw(entry).await?;
+        match entry.method {
+            STORED => {
+                futures::io::copy(StreamReader::from_blob(&raw), writer)
+                    .await
+                    .map_err(|e| write_error(entry, e))?;
+            }
+            DEFLATED => inflate(&raw, entry, writer).await?,
+            method => return Err(unsupported(entry, method)),
+        }
+        writer.close().await.map_err(|e| write_error(entry, e))
+    }
+}
+
+fn unsupported(entry: &IndexEntry, method: u16) -> JsValue {
+    JsValue::from_str(&format!("{} uses compression method {}, which is not supported", entry.name, method))
+}
+
+fn write_error(entry: &IndexEntry, error: io::Error) -> JsValue {
+    JsValue::from_str(&format!("Error writing {}: {}", entry.name, error))
+}
+
+/// Inflates `raw`, the deflated data of `entry`, into `writer` as it is
+/// read, so that neither the data nor what it inflates to is held at once.
+async fn inflate<W: AsyncWrite + Unpin>(raw: &web_sys::Blob, entry: &IndexEntry, writer: &mut W) -> Result<(), JsValue> {
+    let inflate_error = |e: io::Error| JsValue::from_str(&format!("Error inflating {}: {}", entry.name, e));
+    let mut reader = StreamReader::from_blob(raw);
+    let mut decoder = DeflateDecoder::new(Vec::new());
+    let mut chunk = vec![0; CHUNK_LEN];
+    let mut written = 0;
+    loop {
+        let len = reader.read(&mut chunk).await.map_err(inflate_error)?;
+        if len == 0 {
+            decoder.try_finish().map_err(inflate_error)?;
+        } else {
+            decoder.write_all(&chunk[..len]).map_err(inflate_error)?;
+        }
+        let data = std::mem::take(decoder.get_mut());
+        written += data.len() as u64;
+        if written > entry.size {
+            return Err(malformed(&format!("{} is larger than the central directory says", entry.name)));
+        }
+        writer.write_all(&data).await.map_err(|e| write_error(entry, e))?;
+        if len == 0 {
+            break;
+        }
+    }
+    if written != entry.size {
+        return Err(malformed(&format!("{} is smaller than the central directory says", entry.name)));
+    }
+    Ok(())
+}
+
+/// Collects what is written to it as the parts of a `Blob`.
+struct BlobParts(js_sys::Array);
+
+impl AsyncWrite for BlobParts {
+    fn poll_write(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.0.push(&js_sys::Uint8Array::from(buf));
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[wasm_bindgen]
+impl ArchiveIndex {
+    /// Reads the central directory of the archive in `blob`.
+    pub async fn read(blob: web_sys::Blob) -> Result<ArchiveIndex, JsValue> {
+        let (offset, size, count) = locate_directory(&blob).await?;
+        if offset.checked_add(size).is_none_or(|end| end > blob.size() as u64) {
+            return Err(malformed("the central directory extends past the end"));
+        }
+        let directory = read_range(&blob, offset, offset + size).await?;
+        let entries = parse_directory(&directory, count)?;
+        Ok(ArchiveIndex { blob, entries })
+    }
+
+    /// The entries as `{name, size, kind}` objects, in archive order, `kind`
+    /// being `"file"` or `"directory"` and `size` a `BigInt`, as archives
+    /// may declare any size.
+    #[wasm_bindgen(getter)]
+    pub fn entries(&self) -> Result<OfferEntries, JsValue> {
+        let entries: Vec<_> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let kind = if entry.is_dir() { "directory" } else { "file" };
+                ListedEntry { name: &entry.name, size: entry.size, kind }
+            })
+            .collect();
+        Ok(js::to_js_with_bigints(&entries)?.unchecked_into())
+    }
+
+    /// The entry `name` as a `Blob`, typed by its extension. A stored entry
+    /// is a slice of the archive's `Blob` and not read at all.
+    pub async fn extract(&self, name: String) -> Result<web_sys::Blob, JsValue> {
+        self.extract_entry(self.entry(&name)?).await
+    }
+
+    /// Writes the entry `name` to `stream`, like the writable of a file
+    /// handle from `showSaveFilePicker()`, and closes it.
+    pub async fn extract_to(&self, name: String, stream: WritableStream) -> Result<(), JsValue> {
+        self.write_entry(self.entry(&name)?, &mut StreamWriter::new(&stream)).await
+    }
+
+    /// Extracts the entries named in `names` below `directory`, a directory
+    /// standing for everything in it, and calls `on_progress` like
+    /// `ClientConfig.receive_directory`. Entries are written one at a time,
+    /// as they are read. Entries whose path leaves `directory` are skipped.
+    /// Returns how many files were written.
+    pub async fn extract_into(&self, directory: FileSystemDirectoryHandle, names: js_sys::Array, on_progress: Option<js_sys::Function>) -> Result<u32, JsValue> {
+        let selection = Selection::from_js(&names)?;
+        let picked: Vec<_> = self.entries.iter().filter(|entry| !entry.is_dir() && selection.contains(&entry.name)).collect();
+        let mut written = 0;
+        for entry in &picked {
+            let file = match directory::create_file(&directory, &entry.name).await? {
+                Some(file) => file,
+                None => continue,
+            };
+            let writable = JsFuture::from(file.create_writable()).await?;
+            self.write_entry(entry, &mut StreamWriter::new(&writable.unchecked_into::<WritableStream>())).await?;
+            directory::report_written(on_progress.as_ref(), &entry.name, written, Some(picked.len()));
+            written += 1;
+        }
+        Ok(written as u32)
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::AsyncRead;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::js::js_error;
+
+/// How many bytes are sliced from the file at once, by default.
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// How many slices are read ahead of the one being consumed, by default.
+pub const DEFAULT_READ_AHEAD: usize = 2;
+
+/// Reads a `web_sys::File` slice by slice as an `AsyncRead`, so that the file
+/// never has to be held in memory as a whole.
+///
+/// Offsets are kept as `u64` and handed to `Blob.slice()` as `f64`, which is
+/// exact for every file size a browser can handle.
+pub struct FileWrapper {
+    file: web_sys::File,
+    size: u64,
+    chunk_size: usize,
+    read_ahead: usize,
+    /// Where the next slice to request starts.
+    offset: u64,
+    /// The slices being read, in file order, with their expected length.
+    pending: VecDeque<(JsFuture, usize)>,
+    /// The last slice read, of which `chunk[chunk_offset..]` is not consumed yet.
+    chunk: Vec<u8>,
+    chunk_offset: usize,
+}
+
+impl FileWrapper {
+    pub fn new(file: web_sys::File) -> Self {
+        FileWrapper {
+            size: file.size() as u64,
+            file,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            read_ahead: DEFAULT_READ_AHEAD,
+            offset: 0,
+            pending: VecDeque::new(),
+            chunk: Vec::new(),
+            chunk_offset: 0,
+        }
+    }
+
+    /// Sets how many bytes are sliced from the file at once.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Sets how many slices are requested ahead of the one being consumed.
+    /// With `0`, every slice is only requested once the previous one is used up.
+    pub fn read_ahead(mut self, read_ahead: usize) -> Self {
+        self.read_ahead = read_ahead;
+        self
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Requests slices until `read_ahead + 1` of them are in flight.
+    fn fill_pending(&mut self) -> io::Result<()> {
+        while self.pending.len() <= self.read_ahead && self.offset < self.size {
+            let (start, end) = Self::slice_bounds(self.offset, self.chunk_size, self.size);
+            let blob = self.file.slice_with_f64_and_f64(start, end).map_err(js_error)?;
+            let len = (end - start) as usize;
+            self.pending.push_back((JsFuture::from(blob.array_buffer()), len));
+            self.offset += len as u64;
+        }
+        Ok(())
+    }
+
+    /// The `start` and `end` arguments for `Blob.slice()` when reading at most
+    /// `len` bytes at `offset` from a file of `size` bytes.
+    pub fn slice_bounds(offset: u64, len: usize, size: u64) -> (f64, f64) {
+        let start = offset.min(size);
+        let end = offset.saturating_add(len as u64).min(size);
+        (start as f64, end as f64)
+    }
+}
+
+impl AsyncRead for FileWrapper {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        loop {
+            if self.chunk_offset < self.chunk.len() {
+                let len = buf.len().min(self.chunk.len() - self.chunk_offset);
+                buf[..len].copy_from_slice(&self.chunk[self.chunk_offset..self.chunk_offset + len]);
+                self.chunk_offset += len;
+                return Poll::Ready(Ok(len));
+            }
+
+            if buf.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+
+            self.fill_pending()?;
+
+            let (pending, expected_len) = match self.pending.front_mut() {
+                Some(front) => front,
+                None => return Poll::Ready(Ok(0)),
+            };
+            let expected_len = *expected_len;
+            let result = match Pin::new(pending).poll(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(result) => result,
+            };
+            self.pending.pop_front();
+
+            let array = js_sys::Uint8Array::new(&result.map_err(js_error)?);
+            if array.length() as usize != expected_len {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "File changed while it was being read",
+                )));
+            }
+
+            // Reuse the buffer, the slices all have the same size
+            self.chunk.resize(expected_len, 0);
+            array.copy_to(&mut self.chunk);
+            self.chunk_offset = 0;
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use wasm_bindgen::prelude::*;
+
+/// Longest file name most file systems accept, in bytes.
+const MAX_LENGTH: usize = 255;
+
+/// Turns a file name chosen by the peer into one that is safe to save
+/// under: only the last path component is kept, control characters and
+/// characters Windows reserves are replaced, and the name is shortened to
+/// 255 bytes, keeping its extension. Names that end up empty, `.` or `..`
+/// become `file`.
+#[wasm_bindgen]
+pub fn sanitize_filename(name: &str) -> String {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            c if c.is_control() => '_',
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c => c,
+        })
+        .collect();
+    let name = name.trim().trim_end_matches('.');
+
+    if name.is_empty() || name.chars().all(|c| c == '.') {
+        return "file".to_owned();
+    }
+    truncate(name)
+}
+
+fn truncate(name: &str) -> String {
+    if name.len() <= MAX_LENGTH {
+        return name.to_owned();
+    }
+    let (stem, extension) = match name.rfind('.') {
+        Some(dot) if dot > 0 && name.len() - dot <= 16 => name.split_at(dot),
+        _ => (name, ""),
+    };
+    let mut end = MAX_LENGTH - extension.len();
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", &stem[..end], extension)
+}
@@ -0,0 +1,57 @@
+//! Helpers for crossing between JS values and Rust ones.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::AsyncRead;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// A rejected promise or thrown exception as an `io::Error`, for the
+/// `AsyncRead`s and `AsyncWrite`s over JS objects.
+pub(crate) fn js_error(error: JsValue) -> io::Error {
+    io::Error::other(format!("{:?}", error))
+}
+
+/// `value` as the plain JS value `JSON.parse` would give for it, with maps
+/// as objects and `None` as `null`.
+pub(crate) fn to_js<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, serde_wasm_bindgen::Error> {
+    value.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+}
+
+/// Like `to_js`, but with 64 bit integers as `BigInt`s, for values holding
+/// sizes the peer declared; see `size`.
+pub(crate) fn to_js_with_bigints<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, serde_wasm_bindgen::Error> {
+    value.serialize(&serde_wasm_bindgen::Serializer::json_compatible().serialize_large_number_types_as_bigints(true))
+}
+
+/// Like `to_js`, for values handed to listeners and callbacks, which have
+/// no way to fail: a value that cannot be converted is logged and passed as
+/// `undefined`.
+pub(crate) fn to_js_or_undefined<T: Serialize + ?Sized>(value: &T) -> JsValue {
+    to_js(value).unwrap_or_else(|e| {
+        console_log!("Error converting a value for JS: {}", e);
+        JsValue::UNDEFINED
+    })
+}
+
+pub(crate) fn from_js<T: DeserializeOwned>(value: JsValue) -> Result<T, serde_wasm_bindgen::Error> {
+    serde_wasm_bindgen::from_value(value)
+}
+
+/// Marks a reader over JS objects as `Send`, which magic-wormhole requires
+/// of the file it sends. Builds without atomics have a single thread, so
+/// the reader never leaves it.
+pub(crate) struct AssumeSend<T>(pub(crate) T);
+
+// SAFETY: without atomics there are no other threads to send it to.
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl<T> Send for AssumeSend<T> {}
+
+impl<T: AsyncRead + Unpin> AsyncRead for AssumeSend<T> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
@@ -0,0 +1,45 @@
+use magic_wormhole::{Key, KeyPurpose, Wormhole, WormholeKey};
+use wasm_bindgen::prelude::*;
+
+use crate::PairingSeed;
+
+/// The purpose of the subkeys handed to applications.
+#[derive(Debug)]
+struct AppKey;
+
+impl KeyPurpose for AppKey {}
+
+/// The key both sides of a wormhole agreed on. Applications can derive
+/// their own keys from it, for side channels the wormhole doesn't carry.
+#[wasm_bindgen]
+pub struct SessionKey {
+    key: Key<WormholeKey>,
+}
+
+impl SessionKey {
+    pub(crate) fn new(wormhole: &Wormhole) -> Self {
+        SessionKey {
+            key: Key::new(Box::new(**wormhole.key())),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SessionKey {
+    /// Derives a 32 byte subkey for `purpose`. Both sides get the same bytes
+    /// for the same purpose string, different purposes give unrelated keys.
+    #[wasm_bindgen(js_name = deriveKey)]
+    pub fn derive_key(&self, purpose: &str) -> js_sys::Uint8Array {
+        let subkey = self.key.derive_subkey_from_purpose::<AppKey>(purpose);
+        js_sys::Uint8Array::from(subkey.as_slice())
+    }
+
+    /// A seed to connect to the same peer again later, see `PairingSeed`.
+    #[wasm_bindgen(js_name = pairingSeed)]
+    pub fn pairing_seed(&self) -> PairingSeed {
+        let subkey = self.key.derive_subkey_from_purpose::<AppKey>("magic-wormhole-wasm/pairing-seed");
+        let mut seed = [0; 32];
+        seed.copy_from_slice(subkey.as_slice());
+        PairingSeed::new(seed)
+    }
+}
@@ -1,15 +1,24 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
 use std::future::Future;
-use std::path::PathBuf;
-use std::pin::Pin;
-use std::task::{Context, Poll};
+use std::rc::Rc;
 
-use magic_wormhole::{Code, transfer, transit, Wormhole, WormholeError, AppID, AppConfig, transfer::AppVersion};
+use futures::future::{Either, FutureExt};
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use magic_wormhole::{transfer, AppConfig, AppID, Code, MailboxConnection, Wormhole, WormholeError};
+use magic_wormhole::transfer::TransferError;
+use magic_wormhole::transit::{self, Abilities};
 use wasm_bindgen::prelude::*;
-use std::{borrow::Cow, alloc::*};
+use wasm_bindgen::JsCast;
 
-#[cfg(feature = "wee_alloc")]
-#[global_allocator]
-static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
+use context::TransferContext;
+use endpoint::Server;
+use events::Events;
+use metadata::{EntryMetadata, FileMetadata};
+use phase::Phase;
+use stats::{ProgressInterval, StatsRecorder};
+use timeout::{Timeout, Timeouts, Watchdog};
+use trace::Direction;
 
 #[wasm_bindgen]
 extern {
@@ -22,9 +31,114 @@ extern {
 macro_rules! console_log {
     // Note that this is using the `log` function imported above during
     // `bare_bones`
-    ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
+    ($($t:tt)*) => ($crate::log(&format_args!($($t)*).to_string()))
+}
+
+mod abilities;
+mod backoff;
+pub mod api;
+pub mod archive;
+mod bench;
+mod builder;
+mod cancel;
+mod capture;
+mod code;
+mod compression;
+mod context;
+mod directory;
+mod download;
+mod endpoint;
+mod events;
+mod extension;
+mod extract;
+mod file;
+mod filename;
+mod hash;
+mod js;
+mod key;
+mod logging;
+mod mailbox;
+mod metadata;
+mod mime;
+mod manager;
+mod network;
+mod node;
+mod offer;
+mod opfs;
+mod partial;
+mod pause;
+mod perf;
+mod received;
+mod phase;
+mod preview;
+mod relay;
+mod report;
+mod runtime;
+mod saved;
+mod seed;
+mod session;
+pub mod size;
+mod stats;
+pub mod stream;
+mod throttle;
+mod timeout;
+mod timer;
+mod trace;
+mod transit_info;
+mod typescript;
+mod unzip;
+mod uri;
+mod verifier;
+mod version;
+mod wordlist;
+mod worker;
+mod writer;
+mod yielding;
+
+pub use abilities::TransitMode;
+pub use archive::ArchiveEntry;
+pub use builder::{ClientConfigBuilder, DEFAULT_APPID, DEFAULT_CODE_LENGTH, DEFAULT_RENDEZVOUS_URL, DEFAULT_TRANSIT_SERVER_URL};
+pub use code::WormholeCode;
+pub use directory::{FileSystemDirectoryHandle, FileSystemFileHandle};
+pub use extract::ArchiveIndex;
+pub use file::FileWrapper;
+pub use filename::sanitize_filename;
+pub use key::SessionKey;
+pub use mailbox::Mailbox;
+pub use node::NodeFileHandle;
+pub use offer::Offer;
+pub use received::ReceiveResult;
+pub use saved::SavedFile;
+pub use seed::PairingSeed;
+pub use manager::TransferManager;
+pub use session::WormholeSession;
+pub use stats::TransferStats;
+pub use transit_info::TransitInfo;
+pub use typescript::{AppVersions, ArchiveEntries, ClientOptions, ErrorReporter, LogCallback, OfferCallback, OfferEntries, PreviewCallback, RelayAuthCallback, TraceCallback, VerifierCallback, WorkerRequest};
+pub use stream::ReadableStream;
+pub use uri::WormholeTransferUri;
+pub use verifier::Verifier;
+
+/// File name of the archive created by `ClientConfig::send_files`.
+const ARCHIVE_NAME: &str = "files.zip";
+
+/// The key of our own entry in the app versions, see `own_app_version`.
+const APP_VERSION_KEY: &str = "magic-wormhole-wasm";
+
+/// Our entry in `app_versions`, for announcing what other clients of this
+/// crate understand. Created if it does not exist yet.
+fn own_app_version(app_versions: &mut serde_json::Value) -> Option<&mut serde_json::Map<String, serde_json::Value>> {
+    app_versions
+        .as_object_mut()?
+        .entry(APP_VERSION_KEY)
+        .or_insert_with(|| serde_json::json!({}))
+        .as_object_mut()
 }
 
+#[cfg(feature = "wee_alloc")]
+#[global_allocator]
+static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
+
 #[wasm_bindgen]
 pub fn init() {
     wasm_logger::init(wasm_logger::Config::default());
@@ -32,250 +146,1797 @@ pub fn init() {
     console_error_panic_hook::set_once();
 }
 
-struct NoOpFuture {}
-
-impl Future for NoOpFuture {
-    type Output = ();
-
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        Poll::Pending
-    }
+/// Like `init`, but logs only records at `level` or above, one of `off`,
+/// `error`, `warn`, `info`, `debug` and `trace`. `module_filters` override
+/// the level for modules and their children, as `module=level` strings like
+/// `magic_wormhole::core=warn`. With `on_log` records are passed to it
+/// instead of the browser console.
+///
+/// Use either this or `init`. Calling this again replaces the options.
+#[wasm_bindgen]
+pub fn init_with_options(level: String, module_filters: Option<js_sys::Array>, on_log: Option<LogCallback>) -> Result<(), JsValue> {
+    logging::install(&level, module_filters, on_log)?;
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+    Ok(())
 }
 
+/// Registers `reporter` to be called with panics and transfer errors as
+/// `{kind, message, backtrace, phase}`, where `kind` is `panic` or `error`
+/// and `phase` is the last phase the transfer reached. Wrong codes,
+/// timeouts and rejections by the peer are not reported. Call it after
+/// `init`, which replaces the panic hook.
 #[wasm_bindgen]
-pub async fn send(file_input: web_sys::HtmlInputElement, output: web_sys::HtmlElement) {
-    let file_list = file_input.files().expect("Failed to get filelist from File Input!");
-    if file_list.length() < 1 || file_list.get(0) == None {
-        alert("Please select at least one valid file.");
-        return;
-    }
+pub fn set_error_reporter(reporter: Option<ErrorReporter>) {
+    report::set_reporter(reporter);
+}
 
-    let file: web_sys::File = file_list.get(0).expect("Failed to get File from filelist!");
+/// The transfers stored by `ClientConfig.receive_to_indexed_db`, as
+/// `{id, filename, filesize, received, complete, updated, chunks}` objects,
+/// where `received` is how many bytes are stored and `updated` when data was
+/// last stored, in milliseconds since the epoch.
+#[wasm_bindgen]
+pub async fn stored_partials() -> Result<JsValue, JsValue> {
+    let records = partial::list().await?;
+    Ok(js::to_js(&records)?)
+}
 
-    match wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await {
-        Ok(file_content) => {
-            let array = js_sys::Uint8Array::new(&file_content);
-            let len = array.byte_length() as u64;
-            let data_to_send: Vec<u8> = array.to_vec();
-            console_log!("Read raw data ({} bytes)", len);
+/// The data stored for the transfer `id` so far as a `File`, or `undefined`
+/// if nothing is stored for it.
+#[wasm_bindgen]
+pub async fn salvage_partial(id: String) -> Result<Option<web_sys::File>, JsValue> {
+    partial::salvage(&id).await
+}
 
-            output.set_inner_text("connecting...");
+#[wasm_bindgen]
+pub async fn delete_partial(id: String) -> Result<(), JsValue> {
+    partial::remove(&id).await
+}
 
-            send_via_wormhole(
-                data_to_send,
-                len,
-                file.name(),
-                &output,
-            ).await
-        }
-        Err(_) => {
-            console_log!("Error reading file");
-        }
-    }
+/// Deletes the stored transfers, complete or not, that were not updated for
+/// `max_age_ms`, and returns how many were deleted.
+#[wasm_bindgen]
+pub async fn collect_stale_partials(max_age_ms: f64) -> Result<u32, JsValue> {
+    partial::collect_stale(max_age_ms).await
 }
 
+/// The JS runtime the module runs in: `window`, `worker`, `node`, `deno` or
+/// `bun`. Only `window` and `worker` have the DOM and file system APIs;
+/// `WormholeSession` works everywhere.
+#[wasm_bindgen]
+pub fn runtime() -> String {
+    runtime::Runtime::detect().as_str().to_owned()
+}
 
+/// What this build is and supports, for showing in an about box and for
+/// debugging interop: `{crate, magic_wormhole, transit_abilities, simd,
+/// app_versions}`, with the versions of this crate and of the magic-wormhole
+/// library, whether it was built for WebAssembly SIMD, and the app versions
+/// announced with all features enabled.
 #[wasm_bindgen]
-pub struct JsConnection {
-    code: Code,
-    // wormholeAddress: u8,
-    kontAddress: u8,
+pub fn version() -> Result<JsValue, JsValue> {
+    Ok(js::to_js(&version::VersionInfo::current())?)
 }
 
 #[wasm_bindgen]
-pub struct Config {
-    appid:                    String,
-    rendezvous_url:           String,
-    transit_server_url:       String,
+#[derive(Clone)]
+pub struct ClientConfig {
+    appid: AppID,
+    rendezvous_urls: Vec<String>,
+    on_rendezvous: Option<js_sys::Function>,
+    on_offer: Option<js_sys::Function>,
+    auto_accept_size: Option<u64>,
+    max_accept_size: Option<u64>,
+    transit_server_urls: Vec<String>,
     passphrase_component_len: usize,
+    abilities: Abilities,
+    relay_timeout_ms: i32,
+    read_ahead: usize,
+    chunk_size: usize,
+    app_versions: serde_json::Value,
+    wordlist: wordlist::Wordlist,
+    compression: bool,
+    max_bytes_per_second: Option<u64>,
+    timeouts: Timeouts,
+    code_expiry_ms: Option<i32>,
+    trace: Option<js_sys::Function>,
+    performance_marks: bool,
+    description: Option<String>,
+    preview: Option<(js_sys::Function, usize)>,
+    send_code: Option<String>,
+    alternate_code_length: Option<usize>,
+    high_entropy_codes: bool,
+    relay_auth: Option<js_sys::Function>,
+    require_tls: bool,
+    progress_interval: ProgressInterval,
+    yield_budget_ms: Option<f64>,
 }
 
-#[wasm_bindgen]
-pub fn create_config(appid: String, rendezvous_url: String, transit_server_url: String, passphrase_component_len: usize) -> Config {
-    Config {
-        appid,
-        rendezvous_url,
-        transit_server_url,
-        passphrase_component_len
+impl ClientConfig {
+    fn app_config(&self, rendezvous_url: &str) -> AppConfig<serde_json::Value> {
+        AppConfig {
+            id: self.appid.clone(),
+            rendezvous_url: Cow::from(rendezvous_url.to_owned()),
+            app_version: {
+                let mut app_versions = self.app_versions.clone();
+                if self.compression {
+                    compression::announce(&mut app_versions);
+                }
+                app_versions
+            },
+        }
+    }
+
+    /// Runs `connect` against the configured rendezvous servers in order,
+    /// moving on to the next one (after a growing delay) whenever the server
+    /// connection fails. Other errors are returned right away.
+    async fn connect_with_failover<T, F, Fut>(&self, connect: F) -> Result<T, WormholeError>
+    where
+        F: Fn(AppConfig<serde_json::Value>) -> Fut,
+        Fut: Future<Output = Result<T, WormholeError>>,
+    {
+        let mut backoff_ms = 250;
+        let mut servers = self.rendezvous_urls.iter().peekable();
+        loop {
+            let url = servers.next().expect("at least one rendezvous server is configured");
+            match connect(self.app_config(url)).await {
+                Ok(connected) => {
+                    if let Some(callback) = &self.on_rendezvous {
+                        let _ = callback.call1(&JsValue::NULL, &JsValue::from_str(url));
+                    }
+                    return Ok(connected);
+                }
+                Err(WormholeError::ServerError(e)) if servers.peek().is_some() => {
+                    console_log!("Rendezvous server {} failed: {}", url, e);
+                    timer::sleep(backoff_ms).await;
+                    backoff_ms *= 2;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Claims the nameplate of `code`, with `announce` adding to our app
+    /// versions. Wrong codes make the next claim wait, see `backoff`.
+    /// Returns the welcome message of the server along with the wormhole.
+    async fn claim<A: Fn(&mut serde_json::Value)>(&self, code: &str, announce: A) -> Result<(Option<String>, Wormhole), WormholeError> {
+        let connected = async {
+            let mailbox = self.connect_with_failover(|mut config| {
+                announce(&mut config.app_version);
+                MailboxConnection::connect(config, Code(code.to_owned()), true)
+            }).await?;
+            let welcome = mailbox.welcome().map(str::to_owned);
+            Ok((welcome, Wormhole::connect(mailbox).await?))
+        }.await;
+        match &connected {
+            Ok(_) => backoff::succeeded(),
+            Err(WormholeError::PakeFailed) => {
+                let failures = backoff::failed();
+                console_log!("Wrong code, {} in a row", failures);
+            }
+            Err(_) => (),
+        }
+        connected
+    }
+
+    /// The configured transit relays as hints for the library, ordered by
+    /// `relay::by_latency`, and the first of them, see
+    /// `set_transit_server_urls`.
+    async fn relays(&self) -> Result<(Vec<transit::RelayHint>, url::Url), JsValue> {
+        let mut urls = Vec::with_capacity(self.transit_server_urls.len());
+        for url in &self.transit_server_urls {
+            let url = match &self.relay_auth {
+                Some(callback) => relay::authenticate(callback, url).await?,
+                None => url.clone(),
+            };
+            let url = endpoint::validate(&url, Server::Transit, self.require_tls).map_err(|e| JsValue::from_str(&e))?;
+            urls.push(url::Url::parse(&url).map_err(|_| JsValue::from_str(&format!("Invalid transit server url: {}", url)))?);
+        }
+        let urls = relay::by_latency(&urls, self.relay_timeout_ms).await?;
+        let hints = urls
+            .iter()
+            .map(|url| {
+                transit::RelayHint::from_urls(None, [url.clone()])
+                    .map_err(|e| JsValue::from_str(&format!("Invalid transit server url {}: {}", url, e)))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((hints, urls[0].clone()))
+    }
+
+    /// See `client_init`.
+    fn new(appid: &str, rendezvous_urls: Vec<String>, transit_server_urls: Vec<String>, passphrase_component_len: usize) -> Result<ClientConfig, JsValue> {
+        if rendezvous_urls.is_empty() {
+            return Err(JsValue::from_str("At least one rendezvous url is required"));
+        }
+        Ok(ClientConfig {
+            appid: parse_appid(appid)?,
+            rendezvous_urls: endpoint::validate_all(&rendezvous_urls, Server::Rendezvous, false)?,
+            on_rendezvous: None,
+            on_offer: None,
+            auto_accept_size: None,
+            max_accept_size: None,
+            transit_server_urls: endpoint::validate_all(&transit_server_urls, Server::Transit, false)?,
+            passphrase_component_len,
+            abilities: Abilities::FORCE_RELAY,
+            relay_timeout_ms: 5000,
+            read_ahead: file::DEFAULT_READ_AHEAD,
+            chunk_size: file::DEFAULT_CHUNK_SIZE,
+            app_versions: serde_json::to_value(&transfer::APP_CONFIG.app_version).unwrap(),
+            wordlist: wordlist::Wordlist::default(),
+            compression: false,
+            max_bytes_per_second: None,
+            timeouts: Timeouts::default(),
+            code_expiry_ms: None,
+            trace: None,
+            performance_marks: false,
+            description: None,
+            preview: None,
+            send_code: None,
+            alternate_code_length: None,
+            high_entropy_codes: false,
+            relay_auth: None,
+            require_tls: false,
+            progress_interval: ProgressInterval::default(),
+            yield_budget_ms: Some(yielding::DEFAULT_YIELD_BUDGET_MS),
+        })
     }
 }
 
 #[wasm_bindgen]
-pub async fn create_connection(cfg: &Config) -> JsConnection {
-    let mut config = transfer::APP_CONFIG;
-    config.id = AppID::from(cfg.appid.clone());
-    config.rendezvous_url = Cow::from(cfg.rendezvous_url.clone());
-    let connect = Wormhole::connect_without_code(config, cfg.passphrase_component_len);
+impl ClientConfig {
+    /// Fails if `appid` is empty or contains whitespace once surrounding
+    /// whitespace has been trimmed, or if a URL is malformed or cannot be
+    /// used from this page. The error names the URL. The rendezvous server
+    /// needs a `ws` or `wss` URL with a host, the transit relay may also be
+    /// `tcp://host:port`. `http` and `https` URLs are taken as
+    /// `ws` and `wss`.
+    ///
+    /// `builder` and `from_js` name these arguments and have defaults for
+    /// them.
+    pub fn client_init(appid: String, rendezvous_url: String, transit_server_url: String, passphrase_component_len: usize) -> Result<ClientConfig, JsValue> {
+        ClientConfig::new(&appid, vec![rendezvous_url], vec![transit_server_url], passphrase_component_len)
+    }
+
+    /// A builder starting from the public servers, see `ClientConfigBuilder`.
+    pub fn builder() -> ClientConfigBuilder {
+        ClientConfigBuilder::default()
+    }
+
+    /// A config made from a plain object like `{ rendezvous: "wss://…",
+    /// codeLength: 3 }`. Missing options take the builder's defaults, and
+    /// unknown ones are an error.
+    pub fn from_js(options: ClientOptions) -> Result<ClientConfig, JsValue> {
+        let options = builder::Options::from_js(options.into())?;
+        ClientConfigBuilder::from(options).build()
+    }
+
+    /// Replaces the rendezvous servers with `urls`. They are tried in order
+    /// until one of them accepts the connection.
+    pub fn set_rendezvous_urls(&mut self, urls: js_sys::Array) -> Result<(), JsValue> {
+        let urls: Vec<String> = urls
+            .iter()
+            .map(|url| url.as_string().ok_or_else(|| JsValue::from_str("Rendezvous urls must be strings")))
+            .collect::<Result<_, _>>()?;
+        if urls.is_empty() {
+            return Err(JsValue::from_str("At least one rendezvous url is required"));
+        }
+        self.rendezvous_urls = endpoint::validate_all(&urls, Server::Rendezvous, self.require_tls)?;
+        Ok(())
+    }
+
+    /// A copy of this config that uses the rendezvous server named by `uri`,
+    /// if any, to receive the code it carries. Fails if that server cannot
+    /// be used, like one set with `set_rendezvous_urls`.
+    pub fn for_uri(&self, uri: &WormholeTransferUri) -> Result<ClientConfig, JsValue> {
+        let mut config = self.clone();
+        if let Some(url) = uri.rendezvous_url() {
+            config.rendezvous_urls = endpoint::validate_all(&[url], Server::Rendezvous, self.require_tls)?;
+        }
+        Ok(config)
+    }
+
+    /// A copy of this config, to change for some transfers while the
+    /// original stays as it is.
+    #[wasm_bindgen(js_name = clone)]
+    pub fn copy(&self) -> ClientConfig {
+        self.clone()
+    }
+
+    /// A copy of this config with the given options replaced, e.g.
+    /// `config.with_options({ transit: "wss://other.example" })` to use
+    /// another relay for one transfer. Takes the options of `from_js`.
+    pub fn with_options(&self, options: ClientOptions) -> Result<ClientConfig, JsValue> {
+        let options = builder::Options::from_js(options.into())?;
+        let mut config = self.clone();
+        options.apply(&mut config)?;
+        Ok(config)
+    }
+
+    /// Fails like `client_init` for an invalid `appid`.
+    pub fn set_appid(&mut self, appid: String) -> Result<(), JsValue> {
+        self.appid = parse_appid(&appid)?;
+        Ok(())
+    }
+
+    /// How many words the codes we allocate have.
+    pub fn set_code_length(&mut self, words: usize) -> Result<(), JsValue> {
+        if words == 0 {
+            return Err(JsValue::from_str("Codes need at least one word"));
+        }
+        self.passphrase_component_len = words;
+        Ok(())
+    }
+
+    /// Registers a callback that is called with the url of the rendezvous
+    /// server a session ended up using.
+    pub fn set_rendezvous_callback(&mut self, callback: Option<js_sys::Function>) {
+        self.on_rendezvous = callback;
+    }
+
+    /// Sets a short note, like "invoices for March", that is shown to the
+    /// receivers of the files sent with this config along with the offer.
+    /// At most 1024 bytes.
+    pub fn set_description(&mut self, description: Option<String>) -> Result<(), JsValue> {
+        if description.as_ref().is_some_and(|description| description.len() > metadata::MAX_DESCRIPTION_LEN) {
+            return Err(JsValue::from_str(&format!("The description must not be longer than {} bytes", metadata::MAX_DESCRIPTION_LEN)));
+        }
+        self.description = description;
+        Ok(())
+    }
+
+    /// Makes senders use `code` rather than allocating one, like `wormhole
+    /// send --code`, for peers that agreed on it beforehand, e.g. with
+    /// `PairingSeed.code`. The receiver enters it as usual. Either side may
+    /// connect first, and the sender only hears from the rendezvous server
+    /// once the receiver is there.
+    pub fn set_send_code(&mut self, code: Option<String>) -> Result<(), JsValue> {
+        self.send_code = match code {
+            Some(code) => Some(WormholeCode::parse(&code)?.to_code_string()),
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Registers a callback for access-controlled transit relays. Before
+    /// each transfer it is called with every configured relay URL and
+    /// returns (or resolves to) the URL to connect to instead, e.g. with a
+    /// short-lived token in its query. Browsers cannot set headers on
+    /// WebSockets, so the URL is the only place for credentials. The
+    /// resulting URL is also sent to the peer as a relay hint.
+    pub fn set_relay_auth_callback(&mut self, callback: Option<RelayAuthCallback>) {
+        self.relay_auth = callback.map(JsCast::unchecked_into);
+    }
+
+    /// Refuses the unencrypted `ws` and `tcp` schemes from now on, for
+    /// the rendezvous and transit servers as well as the URLs returned by
+    /// the relay auth callback. Fails, and stays off, if one of the URLs
+    /// already configured is unencrypted.
+    pub fn set_require_tls(&mut self, require_tls: bool) -> Result<(), JsValue> {
+        if require_tls {
+            endpoint::validate_all(&self.rendezvous_urls, Server::Rendezvous, true)?;
+            endpoint::validate_all(&self.transit_server_urls, Server::Transit, true)?;
+        }
+        self.require_tls = require_tls;
+        Ok(())
+    }
+
+    /// Makes senders use a fresh random code like `1234567-` followed by 26
+    /// base32 characters for each file, instead of one from the word list.
+    /// Such codes are meant to be shared as a link (`WormholeTransferUri`)
+    /// or QR code rather than typed, and resist online guessing far better.
+    /// The nameplate is picked by the sender, as with `set_send_code`, which
+    /// takes precedence.
+    pub fn set_high_entropy_codes(&mut self, enabled: bool) {
+        self.high_entropy_codes = enabled;
+    }
+
+    /// Makes senders allocate a second code with `words` words for each
+    /// file, e.g. a long one for a QR code next to the short one to type.
+    /// It is reported with the `alternate-code` event, and the transfer
+    /// goes to whichever peer connects first. Not used with
+    /// `set_send_code` or `set_high_entropy_codes`.
+    pub fn set_alternate_code_length(&mut self, words: Option<usize>) -> Result<(), JsValue> {
+        if words == Some(0) {
+            return Err(JsValue::from_str("The alternate code needs at least one word"));
+        }
+        self.alternate_code_length = words;
+        Ok(())
+    }
+
+    /// Registers a callback that gets the first `size` bytes (64 KiB by
+    /// default) of every received file as a `Uint8Array` as soon as they
+    /// arrived, with the file name and size (a `BigInt`), while the rest is
+    /// still being received. Shorter files are passed whole once complete.
+    /// Returning (or resolving to) `false` cancels the transfer.
+    pub fn set_preview_callback(&mut self, callback: Option<PreviewCallback>, size: Option<u32>) -> Result<(), JsValue> {
+        let size = match size {
+            Some(0) => return Err(JsValue::from_str("The preview size must be at least 1 byte")),
+            Some(size) => size as usize,
+            None => preview::DEFAULT_PREVIEW_SIZE,
+        };
+        self.preview = callback.map(|callback| (callback.unchecked_into(), size));
+        Ok(())
+    }
+
+    /// Registers a callback that is asked, with the file name, the size (a
+    /// `BigInt`), the sender's description, if any, and all of that as an
+    /// `Offer`, whether an offered file should be received. Returning (or
+    /// resolving to) `false` or a string with the reason rejects the offer.
+    /// For an archive, returning an array of entry names accepts it and
+    /// makes `receive_archive` and `receive_directory` unpack only those
+    /// entries; other receives keep the whole archive. Without a callback
+    /// every offer is accepted.
+    pub fn set_offer_callback(&mut self, callback: Option<OfferCallback>) {
+        self.on_offer = callback.map(JsCast::unchecked_into);
+    }
+
+    /// Accepts offers of up to `max_size` bytes without asking the offer
+    /// callback, for unattended receivers. Larger offers still go to the
+    /// callback, or are rejected if there is none.
+    pub fn set_auto_accept_size(&mut self, max_size: Option<f64>) -> Result<(), JsValue> {
+        self.auto_accept_size = max_size.map(|size| size::from_js(size, "The auto-accept size")).transpose()?;
+        Ok(())
+    }
+
+    /// Rejects offers of more than `max_size` bytes before anything is
+    /// received. Received files are held in memory, so this bounds what a
+    /// sender can make the page allocate. The sender sees the transfer as
+    /// rejected.
+    pub fn set_max_accept_size(&mut self, max_size: Option<f64>) -> Result<(), JsValue> {
+        self.max_accept_size = max_size.map(|size| size::from_js(size, "The maximum accepted size")).transpose()?;
+        Ok(())
+    }
+
+    /// Replaces the transit relays with `urls`. When more than one relay is
+    /// given, the WebSocket ones are probed at once and offered to the peer
+    /// in the order they connected, followed by the relays that are not
+    /// WebSocket ones or did not connect. magic-wormhole tries the first two.
+    pub fn set_transit_server_urls(&mut self, urls: js_sys::Array) -> Result<(), JsValue> {
+        let urls: Vec<String> = urls
+            .iter()
+            .map(|url| url.as_string().ok_or_else(|| JsValue::from_str("Transit server urls must be strings")))
+            .collect::<Result<_, _>>()?;
+        self.transit_server_urls = endpoint::validate_all(&urls, Server::Transit, self.require_tls)?;
+        Ok(())
+    }
+
+    /// How long a single transit relay may take to accept a connection.
+    /// Relays are probed together, so this bounds the wait for all of them.
+    pub fn set_relay_timeout(&mut self, timeout_ms: i32) {
+        self.relay_timeout_ms = timeout_ms;
+    }
+
+    /// How many slices of a file are read ahead while sending. Larger values
+    /// keep the transit connection busy at the cost of memory.
+    pub fn set_read_ahead(&mut self, read_ahead: usize) {
+        self.read_ahead = read_ahead;
+    }
+
+    /// How many bytes are sliced from a file per read while sending. Smaller
+    /// values use less memory, larger ones need fewer round trips.
+    pub fn set_chunk_size(&mut self, chunk_size: usize) {
+        self.chunk_size = chunk_size;
+    }
+
+    /// Selects which transit connection types are offered. Defaults to
+    /// `TransitMode.RelayOnly`.
+    pub fn set_transit_mode(&mut self, mode: TransitMode) {
+        self.abilities = mode.into();
+    }
+
+    /// Offers exactly the given transit abilities, e.g. `["relay-v1"]`.
+    pub fn set_transit_abilities(&mut self, names: js_sys::Array) -> Result<(), JsValue> {
+        self.abilities = abilities::parse_abilities(&names)?;
+        Ok(())
+    }
+
+    /// Adds the fields of `versions`, a JSON object, to the app versions sent
+    /// to the peer. The peer's app versions are passed to the `connected`
+    /// event, so custom clients can negotiate features.
+    pub fn set_app_versions(&mut self, versions: AppVersions) -> Result<(), JsValue> {
+        let versions: serde_json::Map<String, serde_json::Value> = js::from_js(versions.into())
+            .map_err(|e| JsValue::from_str(&format!("App versions must be a JSON object: {}", e)))?;
+        let mut app_versions = serde_json::to_value(&transfer::APP_CONFIG.app_version).unwrap();
+        if let serde_json::Value::Object(fields) = &mut app_versions {
+            fields.extend(versions);
+        }
+        self.app_versions = app_versions;
+        Ok(())
+    }
+
+    /// Compresses files with gzip while they are sent, if the peer enabled
+    /// compression too. Compressed files are held in memory as a whole, so
+    /// this is meant for large text files over slow connections. Off by
+    /// default.
+    pub fn set_compression(&mut self, enabled: bool) {
+        self.compression = enabled;
+    }
+
+    /// Limits sending and receiving to `max` bytes per second on average, so
+    /// a transfer leaves bandwidth for other traffic. `None` removes the limit.
+    pub fn set_max_bytes_per_second(&mut self, max: Option<f64>) -> Result<(), JsValue> {
+        self.max_bytes_per_second = max.map(|max| size::from_js(max, "The bandwidth limit")).transpose()?;
+        Ok(())
+    }
+
+    /// Gives up on a transfer that takes too long at one stage, with a
+    /// `timeout` event naming it. All limits are in milliseconds and off
+    /// when `None`:
+    ///
+    /// - `peer`: the sender waiting for a receiver to use the code
+    /// - `key_exchange`: the receiver connecting with a code
+    /// - `transit`: setting up the transit connection
+    /// - `stall`: no data moving once the transfer has started
+    pub fn set_timeouts(&mut self, peer: Option<i32>, key_exchange: Option<i32>, transit: Option<i32>, stall: Option<i32>) {
+        self.timeouts = Timeouts {
+            peer_ms: peer,
+            key_exchange_ms: key_exchange,
+            transit_ms: transit,
+            stall_ms: stall,
+            ..self.timeouts
+        };
+    }
+
+    /// Reports a `stalled` event when no data moved for `ms` milliseconds
+    /// while the transfer runs, again after each time data moves and stops
+    /// again. Unlike the `stall` timeout, the transfer goes on; the next
+    /// `progress` event shows that it moves again. transfer-v1 cannot move
+    /// a running transfer to a new transit connection, so a transfer that
+    /// stays stalled can only be cancelled and started over.
+    pub fn set_stall_warning(&mut self, ms: Option<i32>) {
+        self.timeouts.stall_warning_ms = ms;
+    }
+
+    /// Reports `progress` only once `min_ms` milliseconds (100 by default)
+    /// and `min_bytes` bytes (0 by default) passed since the last report,
+    /// instead of for every record, which costs a call into JS each time.
+    /// The first and the last progress are always reported.
+    pub fn set_progress_interval(&mut self, min_ms: Option<f64>, min_bytes: Option<f64>) -> Result<(), JsValue> {
+        self.progress_interval = ProgressInterval {
+            min_ms: min_ms.unwrap_or(stats::DEFAULT_PROGRESS_INTERVAL_MS).max(0.0),
+            min_bytes: min_bytes.map(|bytes| size::from_js(bytes, "The progress byte interval")).transpose()?.unwrap_or(0),
+        };
+        Ok(())
+    }
+
+    /// Lets the browser handle input and render at least every `ms`
+    /// milliseconds (50 by default) while a transfer runs, which keeps the
+    /// page responsive on the main thread when data arrives faster than it
+    /// is written. Each turn costs a round trip through the task queue, so a
+    /// smaller budget costs throughput. Turns are taken with a
+    /// `MessageChannel` message rather than a timer, which keeps transfers
+    /// in hidden tabs at full speed. `None` never yields on purpose, for use
+    /// in workers.
+    pub fn set_yield_budget(&mut self, ms: Option<f64>) {
+        self.yield_budget_ms = ms.filter(|ms| *ms >= 0.0);
+    }
+
+    /// Makes codes allocated for sending expire after `ms` milliseconds
+    /// unless a receiver used them. The remaining time is reported with
+    /// `countdown` events. An expired code ends the session, dropping the
+    /// rendezvous connection; the server frees the nameplate on its own.
+    pub fn set_code_expiry(&mut self, ms: Option<i32>) {
+        self.code_expiry_ms = ms;
+    }
+
+    /// Registers a callback that is passed every event of a transfer as a
+    /// structured record, for debugging interop with other clients. See
+    /// `TraceRecord` for the fields.
+    pub fn set_trace(&mut self, callback: Option<TraceCallback>) {
+        self.trace = callback.map(JsCast::unchecked_into);
+    }
+
+    /// Adds `performance.mark` and `performance.measure` entries for the
+    /// phases of each transfer, to see in the browser's performance tools
+    /// where the time goes. See `perf::Marked` for the names.
+    pub fn set_performance_marks(&mut self, enabled: bool) {
+        self.performance_marks = enabled;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn appid(&self) -> String {
+        self.appid.to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rendezvous_urls(&self) -> js_sys::Array {
+        self.rendezvous_urls.iter().map(|url| JsValue::from_str(url)).collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn transit_server_urls(&self) -> js_sys::Array {
+        self.transit_server_urls.iter().map(|url| JsValue::from_str(url)).collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn code_length(&self) -> usize {
+        self.passphrase_component_len
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn relay_timeout(&self) -> i32 {
+        self.relay_timeout_ms
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn read_ahead(&self) -> usize {
+        self.read_ahead
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn compression(&self) -> bool {
+        self.compression
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn require_tls(&self) -> bool {
+        self.require_tls
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn high_entropy_codes(&self) -> bool {
+        self.high_entropy_codes
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn description(&self) -> Option<String> {
+        self.description.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn auto_accept_size(&self) -> Option<f64> {
+        self.auto_accept_size.map(|size| size as f64)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_accept_size(&self) -> Option<f64> {
+        self.max_accept_size.map(|size| size as f64)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_bytes_per_second(&self) -> Option<f64> {
+        self.max_bytes_per_second.map(|max| max as f64)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn code_expiry(&self) -> Option<i32> {
+        self.code_expiry_ms
+    }
 
-    // haven't serialized error types yet, so i just unwrap everything for now
-    let (welcome, kont) = connect.await.unwrap();
-    // let wormhole = kont.await.unwrap();
+    /// Sends the first file selected in `file_input`.
+    ///
+    /// `on_verifier` is called with the session `Verifier` once the peer has
+    /// connected; see `Verifier` for how it can gate the transfer.
+    /// `chunk_size` overrides the configured chunk size for this transfer.
+    pub async fn send(&self, file_input: web_sys::HtmlInputElement, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>, chunk_size: Option<usize>) -> Option<TransferStats> {
+        let file_list = file_input.files().expect("Failed to get filelist from File Input!");
+        if file_list.length() < 1 || file_list.get(0).is_none() {
+            alert("Please select at least one valid file.");
+            return None;
+        }
+
+        let file: web_sys::File = file_list.get(0).expect("Failed to get File from filelist!");
+        self.send_file(file, &TransferContext::new(Rc::new(output), on_verifier), chunk_size).await
+    }
+
+    /// Receives a file using `code`.
+    ///
+    /// `on_verifier` is called with the session `Verifier` before the offer is
+    /// accepted; see `Verifier` for how it can gate the transfer. If
+    /// `expected_sha256` is given, the transfer fails unless the received
+    /// data has that SHA-256 (as hex).
+    pub async fn receive(&self, code: String, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>, expected_sha256: Option<String>) -> Option<ReceiveResult> {
+        let context = TransferContext {
+            expected_sha256,
+            ..TransferContext::new(Rc::new(output), on_verifier)
+        };
+        self.receive_file(code, &context).await
+    }
+
+    /// Sends all `files` (an array of `File`s) as a single zip archive,
+    /// reading each file as the archive gets to it.
+    pub async fn send_files(&self, files: js_sys::Array, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>) -> Option<TransferStats> {
+        let context = TransferContext::new(Rc::new(output), on_verifier);
+        let mut entries = Vec::new();
+        for file in files.iter() {
+            let file: web_sys::File = match file.dyn_into() {
+                Ok(file) => file,
+                Err(_) => {
+                    alert("Please select only valid files.");
+                    return None;
+                }
+            };
+            entries.push(self.pack_entry(file.name(), file));
+        }
+
+        self.send_archive(entries, ARCHIVE_NAME.to_owned(), &context).await
+    }
+
+    /// Sends all files below `directory` as a zip archive named after it.
+    ///
+    /// The archive goes out as a regular file offer, not as the directory
+    /// offer of transfer-v1: peers receive `<directory>.zip` rather than an
+    /// unpacked directory, see the README. Files are read as the archive
+    /// gets to them rather than held in memory all at once.
+    pub async fn send_directory(&self, directory: FileSystemDirectoryHandle, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>) -> Option<TransferStats> {
+        let context = TransferContext::new(Rc::new(output), on_verifier);
+        let mut entries = Vec::new();
+        if let Err(e) = directory::read_directory(&directory, String::new(), &mut entries).await {
+            context.events.error(&format!("Error reading directory: {:?}", e));
+            return None;
+        }
+        let entries = entries.into_iter().map(|(name, file)| self.pack_entry(name, file)).collect();
+
+        self.send_archive(entries, format!("{}.zip", directory.name()), &context).await
+    }
+
+    /// Sends the chunks of `stream` as a file named `name`. `size` must be
+    /// the exact number of bytes the stream yields.
+    pub async fn send_stream(&self, name: String, size: f64, stream: ReadableStream, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>) -> Option<TransferStats> {
+        let context = TransferContext::new(Rc::new(output), on_verifier);
+        let size = match size::from_js(size, "The stream size") {
+            Ok(size) => size,
+            Err(e) => {
+                context.events.error(&e.as_string().unwrap_or_default());
+                return None;
+            }
+        };
+        self.send_via_wormhole(&mut stream::StreamReader::new(&stream), size, name, &FileMetadata::default(), &context).await
+    }
+
+    /// Sends the content of `blob` as a file named `name`.
+    pub async fn send_blob(&self, name: String, blob: web_sys::Blob, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>) -> Option<TransferStats> {
+        self.send_via_wormhole(
+            &mut stream::StreamReader::from_blob(&blob),
+            blob.size() as u64,
+            name,
+            &FileMetadata::of_blob(&blob),
+            &TransferContext::new(Rc::new(output), on_verifier),
+        ).await
+    }
+
+    /// Offers `file` to one receiver after the other, with a new code for
+    /// every transfer. Stops after `max_count` transfers, successful or not,
+    /// and allocates no new code once `max_duration_ms` has passed. Returns
+    /// how many receivers got the file.
+    pub async fn send_many(&self, file: web_sys::File, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>, max_count: u32, max_duration_ms: Option<f64>) -> u32 {
+        let context = TransferContext::new(Rc::new(output), on_verifier);
+        let deadline = max_duration_ms.map(|duration| js_sys::Date::now() + duration);
+        let mut sent = 0;
+        for round in 0..max_count {
+            if deadline.is_some_and(|deadline| js_sys::Date::now() >= deadline) {
+                break;
+            }
+            if self.send_file(file.clone(), &context, None).await.is_some() {
+                sent += 1;
+            }
+            console_log!("Sent {} copies in {} transfers", sent, round + 1);
+        }
+        sent
+    }
+
+    /// Sends the text on the clipboard as a file named `name`, or
+    /// `clipboard.txt`. Has to be called from a user gesture like a click,
+    /// or the browser refuses to read the clipboard.
+    pub async fn send_text_from_clipboard(&self, name: Option<String>, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>) -> Option<TransferStats> {
+        let context = TransferContext::new(Rc::new(output), on_verifier);
+        let blob = match capture::clipboard_text().await {
+            Ok(blob) => blob,
+            Err(e) => {
+                context.events.error(&format!("Error reading the clipboard: {:?}", e));
+                return None;
+            }
+        };
+        let name = name.unwrap_or_else(|| "clipboard.txt".to_owned());
+        self.send_via_wormhole(&mut stream::StreamReader::from_blob(&blob), blob.size() as u64, name, &FileMetadata::of_blob(&blob), &context).await
+    }
+
+    /// Sends the content of `canvas` as an image named `name`, encoded as
+    /// `mime_type` (`image/png` by default, browsers also support
+    /// `image/jpeg` and usually `image/webp`).
+    pub async fn send_canvas(&self, canvas: web_sys::HtmlCanvasElement, name: String, mime_type: Option<String>, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>) -> Option<TransferStats> {
+        let context = TransferContext::new(Rc::new(output), on_verifier);
+        let blob = match capture::canvas_blob(&canvas, mime_type.as_deref().unwrap_or("image/png")).await {
+            Ok(blob) => blob,
+            Err(e) => {
+                context.events.error(&format!("Error encoding the canvas: {:?}", e));
+                return None;
+            }
+        };
+        self.send_via_wormhole(&mut stream::StreamReader::from_blob(&blob), blob.size() as u64, name, &FileMetadata::of_blob(&blob), &context).await
+    }
+
+    /// Sends `size` bytes of random data through the configured servers to
+    /// a second session in this page, to check a deployment and measure
+    /// what speed to expect from this browser. Resolves to `{bytes,
+    /// elapsed_ms, average_bytes_per_second, peak_bytes_per_second,
+    /// connection, relay_url, code_ms, key_exchange_ms, transit_ms}`, where
+    /// the last three are the milliseconds until the code was allocated, the
+    /// key confirmed and the transit connected. Fails with the first error
+    /// either side reported.
+    ///
+    /// Callbacks, limits and the send code of this config are not used, so
+    /// the test runs without interaction.
+    pub async fn self_test(&self, size: f64) -> Result<JsValue, JsValue> {
+        let size = size::from_js(size, "The self-test size")?;
+        let mut config = self.clone();
+        config.on_offer = None;
+        config.auto_accept_size = None;
+        config.max_accept_size = None;
+        config.preview = None;
+        config.description = None;
+        config.send_code = None;
+        config.high_entropy_codes = false;
+        config.alternate_code_length = None;
+        config.code_expiry_ms = None;
+        bench::run(&config, size).await
+    }
+
+    /// Sends `data` as a file named `name`, for small in-memory payloads.
+    pub async fn send_bytes(&self, name: String, data: js_sys::Uint8Array, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>) -> Option<TransferStats> {
+        self.send_data(name, &data, &TransferContext::new(Rc::new(output), on_verifier)).await
+    }
+
+    /// Receives a file into the Origin Private File System as `name`, or as
+    /// the sanitized name the peer offered, replacing any file of that name.
+    /// The data is written as it arrives, so the file may be larger than the
+    /// memory available. Compression is not used.
+    ///
+    /// In dedicated workers the data goes to the file right away, and when
+    /// the transfer fails the partial file is kept: its size tells how far
+    /// the transfer got. Pages write through a writable stream, which only
+    /// replaces the file once the transfer is complete.
+    pub async fn receive_to_opfs(&self, code: String, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>, name: Option<String>, expected_sha256: Option<String>) -> Option<SavedFile> {
+        let context = TransferContext {
+            expected_sha256,
+            ..TransferContext::new(Rc::new(output), on_verifier)
+        };
+        self.receive_saved(code, &context, |offered| async move { opfs::create(&name.unwrap_or(offered)).await }).await
+    }
+
+    /// Receives a file into IndexedDB under the transfer id `id`, replacing
+    /// what was stored for it before, and returns it as a `File` read back
+    /// from there. The data is stored as it arrives, so when the transfer
+    /// fails `salvage_partial(id)` still has what was received, up to the
+    /// last complete MiB. Stored transfers stay until they are deleted with
+    /// `delete_partial` or `collect_stale_partials`. Compression is not used.
+    pub async fn receive_to_indexed_db(&self, code: String, id: String, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>, expected_sha256: Option<String>) -> Option<web_sys::File> {
+        let context = TransferContext {
+            expected_sha256,
+            ..TransferContext::new(Rc::new(output), on_verifier)
+        };
+        let instrumented = self.instrumented(&context, Direction::In);
+        let context = instrumented.as_ref().unwrap_or(&context);
+        let received = self.uncompressed().receive_into(code, context, |offered, _| {
+            let name = sanitize_filename(&offered.name());
+            let filesize = offered.size();
+            async move {
+                partial::PartialWriter::create(id, name, filesize)
+                    .await
+                    .map_err(|e| format!("Error opening IndexedDB: {:?}", e))
+            }
+        }).await?;
+
+        console_log!("Data stored, length: {}, SHA-256: {}", received.filesize, received.sha256);
+        if !verify_sha256(context, &received.sha256) {
+            return None;
+        }
+        match received.writer.file(&received.metadata).await {
+            Ok(file) => {
+                context.events.phase(Phase::Done);
+                Some(file)
+            }
+            Err(e) => {
+                context.events.error(&format!("Error reading the stored file: {:?}", e));
+                None
+            }
+        }
+    }
+
+    /// Receives a file into `handle`, typically from `showSaveFilePicker()`,
+    /// so that it ends up where the user chose. The data is written to the
+    /// handle's writable stream as it arrives, which replaces the file in
+    /// one go once the transfer is complete and leaves it untouched when
+    /// the transfer fails. Compression is not used.
+    pub async fn receive_to_handle(&self, code: String, handle: FileSystemFileHandle, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>, expected_sha256: Option<String>) -> Option<SavedFile> {
+        let context = TransferContext {
+            expected_sha256,
+            ..TransferContext::new(Rc::new(output), on_verifier)
+        };
+        self.receive_saved(code, &context, |_| async move {
+            let writable = wasm_bindgen_futures::JsFuture::from(handle.create_writable()).await?;
+            Ok(saved::SaveTarget {
+                handle,
+                writer: Box::new(stream::StreamWriter::new(&writable.unchecked_into::<stream::WritableStream>())),
+            })
+        }).await
+    }
+
+    /// Receives a file as a `ReadableStream` of `Uint8Array` chunks, to
+    /// stream it to the browser's download manager through a service worker
+    /// rather than collecting it in memory. Once the offer is accepted,
+    /// `on_stream` is called with the sanitized file name, the size (a
+    /// `BigInt`) and the stream; the transfer waits for it if it returns a
+    /// promise, and rejects the offer if it throws. See the `download`
+    /// module for the message contract with the service worker.
+    ///
+    /// The stream errors if the transfer fails. Compression is not used.
+    pub async fn receive_stream(&self, code: String, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>, on_stream: js_sys::Function) -> Option<TransferStats> {
+        let context = TransferContext::new(Rc::new(output), on_verifier);
+        let instrumented = self.instrumented(&context, Direction::In);
+        let context = instrumented.as_ref().unwrap_or(&context);
+        let received = self.uncompressed().receive_into(code, context, |offered, _| {
+            let name = sanitize_filename(&offered.name());
+            let filesize = offered.size();
+            async move {
+                let (readable, writer) = download::pipe();
+                let handed_off = on_stream.call3(&JsValue::NULL, &JsValue::from_str(&name), &size::declared(filesize), &readable);
+                let result = match handed_off {
+                    Ok(result) => match result.dyn_into::<js_sys::Promise>() {
+                        Ok(promise) => wasm_bindgen_futures::JsFuture::from(promise).await.map(|_| ()),
+                        Err(_) => Ok(()),
+                    },
+                    Err(e) => Err(e),
+                };
+                result
+                    .map(|_| writer)
+                    .map_err(|e| format!("Error in stream callback: {:?}", e))
+            }
+        }).await?;
+
+        console_log!("Data streamed, length: {}, SHA-256: {}", received.filesize, received.sha256);
+        context.events.phase(Phase::Done);
+        Some(received.stats)
+    }
+
+    /// Receives a zip archive like the one created by `send_files` and
+    /// returns its entries as an array of `ArchiveEntry`s.
+    pub async fn receive_files(&self, code: String, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>) -> Option<ArchiveEntries> {
+        let context = TransferContext::new(Rc::new(output), on_verifier);
+        let result = self.receive_file(code, &context).await?;
+        match archive::unpack(&result.data, result.selection.as_ref()) {
+            Ok(entries) => Some(entries.into_iter().map(JsValue::from).collect::<js_sys::Array>().unchecked_into()),
+            Err(e) => {
+                context.events.error(&format!("Received file is not an archive: {}", e));
+                None
+            }
+        }
+    }
 
-    unsafe {
-        let layout: Layout = Layout::new::<std::future::Future<Output = Result<Box<Wormhole>, WormholeError>>>();
-        let ptr = alloc(layout);
-        if ptr.is_null() {
-            handle_alloc_error(layout);
+    /// Receives a zip archive and extracts it below `directory` while it
+    /// arrives, writing each entry as its data comes in.
+    ///
+    /// `on_progress` is called with the name, index and count of the entries
+    /// as each one is written; the count is `undefined` unless the sender
+    /// announced the entries, as `send_files` and `send_directory` do.
+    /// Returns the number of entries on success. Compression is not used.
+    ///
+    /// Offers of zip files are accepted, and so are the directory offers of
+    /// the `wormhole` command line tool, as magic-wormhole hands those over
+    /// as a zip file named `<directory>.zip`. Any other file fails to
+    /// extract once its first bytes arrive.
+    pub async fn receive_directory(&self, code: String, directory: FileSystemDirectoryHandle, output: web_sys::HtmlElement, on_verifier: Option<VerifierCallback>, on_progress: Option<js_sys::Function>) -> Option<u32> {
+        let context = TransferContext::new(Rc::new(output), on_verifier);
+        let instrumented = self.instrumented(&context, Direction::In);
+        let context = instrumented.as_ref().unwrap_or(&context);
+        let (readable, writable) = stream::pipe();
+        // The entries to extract are only known once the offer is accepted
+        let (accepted, picked) = futures::channel::oneshot::channel();
+        let uncompressed = self.uncompressed();
+        let receive = uncompressed.receive_into(code, context, |offered, selection| {
+            let count = offered.metadata().entries.as_ref().map(|entries| {
+                entries.iter().filter(|entry| selection.is_none_or(|selection| selection.contains(&entry.name))).count()
+            });
+            let _ = accepted.send((selection.cloned(), count));
+            async move { Ok(stream::StreamWriter::new(&writable)) }
+        });
+        let extract = async {
+            let (selection, count) = picked.await.ok()?;
+            let mut reader = stream::StreamReader::new(&readable);
+            unzip::extract(&mut reader, &directory, selection.as_ref(), count, on_progress.as_ref()).await
+        };
+        let (received, extracted) = futures::join!(receive, extract);
+
+        let received = received?;
+        match extracted {
+            Some(Ok(written)) if verify_sha256(context, &received.sha256) => {
+                context.events.phase(Phase::Done);
+                Some(written)
+            }
+            Some(Ok(_)) => None,
+            Some(Err(e)) => {
+                context.events.error(&format!("Error extracting the archive: {}", e.as_string().unwrap_or_else(|| format!("{:?}", e))));
+                None
+            }
+            None => None,
         }
+    }
+
+    /// Replaces the PGP word list used by `complete_code`. `lists` is an
+    /// array of word arrays, used in turn for consecutive words of a code;
+    /// pass `[words]` to draw every word from the same list.
+    ///
+    /// Codes are still allocated by magic-wormhole from the PGP word list, so
+    /// a localized list only helps with typing codes from peers that use it.
+    /// The nameplate is unaffected either way.
+    pub fn set_wordlist(&mut self, lists: js_sys::Array) -> Result<(), JsValue> {
+        let lists = lists
+            .iter()
+            .map(|list| {
+                list.dyn_into::<js_sys::Array>()
+                    .map_err(|_| JsValue::from_str("A word list must be an array of words"))?
+                    .iter()
+                    .map(|word| word.as_string().ok_or_else(|| JsValue::from_str("Words must be strings")))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.wordlist = wordlist::Wordlist::new(lists).map_err(|e| JsValue::from_str(&e))?;
+        Ok(())
+    }
 
-        *(ptr as *mut (std::future::Future<Output = Result<Box<Wormhole>, WormholeError>>)) = kont;
+    /// The ways to complete the last word of `code`, as typed so far, into a
+    /// code of the configured length, e.g. `7-guitarist-r` into
+    /// `7-guitarist-rebirth`, `7-guitarist-reform`, ...
+    pub fn complete_code(&self, code: &str) -> js_sys::Array {
+        self.wordlist.complete(code, self.passphrase_component_len)
+            .iter()
+            .map(|candidate| JsValue::from_str(candidate))
+            .collect()
+    }
+
+    /// Allocates a code for a `Mailbox` and waits for a peer to join it.
+    /// `on_code` is called with the code as soon as it is known.
+    pub async fn open_mailbox(&self, on_code: js_sys::Function, on_verifier: Option<VerifierCallback>) -> Result<Mailbox, JsValue> {
+        let mailbox = self
+            .connect_with_failover(|config| MailboxConnection::create(config, self.passphrase_component_len))
+            .await
+            .map_err(mailbox::connection_error)?;
+        on_code.call1(&JsValue::NULL, &JsValue::from_str(mailbox.code().as_ref()))?;
+        let wormhole = Wormhole::connect(mailbox).await.map_err(mailbox::connection_error)?;
+        Mailbox::confirm(wormhole, on_verifier.map(JsCast::unchecked_into).as_ref()).await
+    }
+
+    /// Joins the `Mailbox` a peer opened with `code`.
+    pub async fn join_mailbox(&self, code: String, on_verifier: Option<VerifierCallback>) -> Result<Mailbox, JsValue> {
+        if let Some(remaining_ms) = backoff::remaining_ms() {
+            return Err(JsValue::from_str(&backoff_message(remaining_ms)));
+        }
+        let (_, wormhole) = self
+            .claim(&code, |_| ())
+            .await
+            .map_err(mailbox::connection_error)?;
+        Mailbox::confirm(wormhole, on_verifier.map(JsCast::unchecked_into).as_ref()).await
+    }
 
-        return JsConnection {
-            code: welcome.code,
-            wormholeAddress: *ptr,
+    /// Entry point for running transfers in a dedicated Worker: call it with
+    /// every message the worker receives. See the `worker` module for the
+    /// message protocol. Uses no DOM APIs.
+    pub async fn handle_message(&self, message: WorkerRequest) {
+        let events = Rc::new(worker::WorkerEvents::new());
+        let context = TransferContext::new(events.clone(), None);
+        let request = match worker::Request::parse(&message) {
+            Ok(request) => request,
+            Err(e) => {
+                events.error(&e);
+                return;
+            }
         };
+
+        match request {
+            worker::Request::Send(file) => {
+                if let Some(stats) = self.send_file(file, &context, None).await {
+                    events.sent(stats);
+                }
+            }
+            worker::Request::SendBytes(name, data) => {
+                if let Some(stats) = self.send_data(name, &data, &context).await {
+                    events.sent(stats);
+                }
+            }
+            worker::Request::Receive(code, expected_sha256) => {
+                let context = TransferContext { expected_sha256, ..context };
+                if let Some(result) = self.receive_file(code, &context).await {
+                    events.received(result);
+                }
+            }
+            worker::Request::ReceiveToOpfs(code, name, expected_sha256) => {
+                let context = TransferContext { expected_sha256, ..context };
+                let open = |offered| async move { opfs::create(&name.unwrap_or(offered)).await };
+                if let Some(saved) = self.receive_saved(code, &context, open).await {
+                    events.saved(saved);
+                }
+            }
+        }
     }
 }
 
-#[wasm_bindgen]
-pub async fn send_file(cfg: &Config, filename: String, ptr: u8) {
-    unsafe {
-        let wormhole = &*(ptr as *mut Wormhole);
-        println!("wormhole: {:?}", wormhole);
-    }
-    // let transfer_result = transfer::send_file(
-    //     wormhole,
-    //     url::Url::parse("ws://piegames.de:4002").unwrap(),
-    //     &mut &file[..],
-    //     PathBuf::from(file_name),
-    //     file_size,
-    //     transit::Abilities::FORCE_RELAY,
-    //     |info, address| {
-    //         console_log!("Connected to '{:?}' on address {:?}", info, address);
-    //     },
-    //     |cur, total| {
-    //         console_log!("Progress: {}/{}", cur, total);
-    //     },
-    //     NoOpFuture {},
-    // ).await;
-
-    // match transfer_result {
-    //     Ok(_) => {
-    //         console_log!("Data sent");
-    //     }
-    //     Err(e) => {
-    //         console_log!("Error in data transfer: {:?}", e);
-    //     }
-    // }
+impl ClientConfig {
+    /// Sends a browser `File` in chunks, like `ClientConfig.send`, for the
+    /// Rust API in `api`.
+    pub async fn send_web_file(&self, file: web_sys::File, events: Rc<dyn api::Events>, controls: &api::Controls) -> Option<TransferStats> {
+        self.send_file(file, &controls.context(events, None), None).await
+    }
+
+    async fn send_file(&self, file: web_sys::File, context: &TransferContext, chunk_size: Option<usize>) -> Option<TransferStats> {
+        let name = file.name();
+        let metadata = FileMetadata::of_file(&file);
+        let mut reader = FileWrapper::new(file)
+            .chunk_size(chunk_size.unwrap_or(self.chunk_size))
+            .read_ahead(self.read_ahead);
+        let len = reader.size();
+        console_log!("Sending {} ({} bytes)", name, len);
+
+        self.send_via_wormhole(&mut reader, len, name, &metadata, context).await
+    }
+
+    async fn send_data(&self, name: String, data: &js_sys::Uint8Array, context: &TransferContext) -> Option<TransferStats> {
+        let len = data.byte_length() as u64;
+
+        self.send_via_wormhole(
+            &mut futures::io::Cursor::new(data.to_vec()),
+            len,
+            name,
+            &FileMetadata::default(),
+            context,
+        ).await
+    }
+
+    /// `file` as an archive entry called `name`, read in chunks like a file
+    /// sent on its own.
+    fn pack_entry(&self, name: String, file: web_sys::File) -> archive::PackEntry<FileWrapper> {
+        let reader = FileWrapper::new(file).chunk_size(self.chunk_size).read_ahead(self.read_ahead);
+        archive::PackEntry { name, size: reader.size(), reader }
+    }
+
+    async fn send_archive<R: AsyncRead + Unpin>(&self, entries: Vec<archive::PackEntry<R>>, name: String, context: &TransferContext) -> Option<TransferStats> {
+        let metadata = FileMetadata {
+            mime_type: Some(mime::from_filename(ARCHIVE_NAME).to_owned()),
+            entries: Some(entries.iter().map(|entry| EntryMetadata { name: entry.name.clone(), size: entry.size }).collect()),
+            ..FileMetadata::default()
+        };
+        let count = entries.len();
+        let mut archive = match archive::ZipStream::new(entries) {
+            Ok(archive) => archive,
+            Err(e) => {
+                context.events.error(&format!("Error creating archive: {}", e));
+                return None;
+            }
+        };
+        let len = archive.size();
+        console_log!("Packing {} files ({} bytes)", count, len);
+
+        self.send_via_wormhole(&mut archive, len, name, &metadata, context).await
+    }
+
+    /// `context` with its events traced, marked and reported, as far as that
+    /// is enabled. `data` is the direction the file flows in.
+    fn instrumented(&self, context: &TransferContext, data: Direction) -> Option<TransferContext> {
+        if self.trace.is_none() && !self.performance_marks && !report::is_set() {
+            return None;
+        }
+        let mut events = context.events.clone();
+        if let Some(sink) = &self.trace {
+            events = Rc::new(trace::Traced::new(events, sink.clone(), data));
+        }
+        if self.performance_marks {
+            events = Rc::new(perf::Marked::new(events));
+        }
+        if report::is_set() {
+            events = Rc::new(report::Reported::new(events));
+        }
+        Some(context.with_events(events))
+    }
+
+    /// Lets `on_verifier` confirm the session with the peer and orders the
+    /// transit relays, see `relays`. `None` means the session ended, after
+    /// reporting why.
+    async fn establish(&self, wormhole: Wormhole, context: &TransferContext) -> Option<(Wormhole, Vec<transit::RelayHint>, url::Url)> {
+        context.events.phase(Phase::PeerConnected);
+        match verifier::confirm(&wormhole, context.on_verifier.as_ref()).await {
+            Ok(true) if context.cancel.is_cancelled() => {
+                context.events.error("Cancelled");
+                let _ = wormhole.close().await;
+                return None;
+            }
+            Ok(true) => {
+                context.events.phase(Phase::KeyConfirmed);
+                context.events.connected(&wormhole);
+            }
+            Ok(false) => {
+                context.events.error("Verification rejected");
+                let _ = wormhole.close().await;
+                return None;
+            }
+            Err(e) => {
+                context.events.error(&format!("Error in verifier callback: {:?}", e));
+                let _ = wormhole.close().await;
+                return None;
+            }
+        }
+
+        match self.relays().await {
+            Ok((hints, url)) => Some((wormhole, hints, url)),
+            Err(e) => {
+                context.events.error(&e.as_string().unwrap_or_default());
+                let _ = wormhole.close().await;
+                None
+            }
+        }
+    }
+
+    /// Receives the file sent with `code` into the writer `open` returns for
+    /// the accepted offer and the archive entries picked from it, if any.
+    /// `open` failing rejects the offer with its message. The writer is
+    /// closed once all data has been written.
+    ///
+    /// `context` is expected to be `instrumented` already.
+    async fn receive_into<W, F, Fut>(&self, code: String, context: &TransferContext, open: F) -> Option<Received<W>>
+    where
+        W: AsyncWrite + Unpin,
+        F: FnOnce(&offer::Offer, Option<&offer::Selection>) -> Fut,
+        Fut: Future<Output = Result<W, String>>,
+    {
+        if let Some(remaining_ms) = backoff::remaining_ms() {
+            context.events.error(&backoff_message(remaining_ms));
+            return None;
+        }
+        let _network = network::Watch::start(context);
+        context.events.status("connecting...");
+        let connect = futures::future::select(Box::pin(self.claim(&code, |_| ())), context.cancel.cancelled());
+        let connected = match timer::within(self.timeouts.key_exchange_ms, connect).await {
+            Ok(Either::Left((connected, _))) => connected,
+            Ok(Either::Right(_)) => {
+                // Dropping the claim closes the rendezvous server connection
+                context.events.error("Cancelled");
+                return None;
+            }
+            Err(timer::TimedOut) => {
+                context.events.timed_out(Timeout::KeyExchange);
+                return None;
+            }
+        };
+
+        let wormhole = match connected {
+            Ok((welcome, wormhole)) => {
+                if let Some(message) = &welcome {
+                    context.events.welcome(message);
+                }
+                wormhole
+            }
+            Err(WormholeError::PakeFailed) => {
+                context.events.wrong_code();
+                return None;
+            }
+            Err(WormholeError::ServerError(e)) => {
+                context.events.error(&format!("The rendezvous server refused the connection: {}", e));
+                return None;
+            }
+            Err(e) => {
+                context.events.error(&format!("Error in connection: {}", e));
+                return None;
+            }
+        };
+        let (wormhole, relay_hints, relay_url) = self.establish(wormhole, context).await?;
+        let compressed = self.compression && compression::negotiated(wormhole.peer_version());
+        // The entries are checked against the offered size, which comes later
+        let peer_version = wormhole.peer_version().clone();
+        let relay = relay_url.to_string();
+        let req = transfer::request_file(
+            wormhole,
+            relay_hints,
+            self.abilities,
+            context.cancel.cancelled(),
+        ).await;
+
+        let req = match req {
+            Ok(Some(req)) => req,
+            Ok(None) => {
+                context.events.error("No file offered");
+                return None;
+            }
+            Err(e) => {
+                context.events.error(&format!("Error waiting for the offer: {}", e));
+                return None;
+            }
+        };
+
+        let name = req.file_name();
+        let filesize = req.file_size();
+        let metadata = FileMetadata::from_peer(&peer_version, Some(filesize).filter(|_| !compressed));
+        let layout = metadata.entries.as_deref().and_then(archive::Layout::new);
+        let stats = Rc::new(StatsRecorder::new(&relay_url, self.progress_interval, layout));
+        console_log!("File name: {:?}, size: {}", name, filesize);
+        context.events.trace(Direction::In, "offer", serde_json::json!({
+            "filename": name,
+            "filesize": filesize,
+        }));
+
+        if let Some(max) = self.max_accept_size.filter(|&max| filesize > max) {
+            context.events.error(&format!("The offered file is {} bytes, more than the limit of {} bytes", filesize, max));
+            let _ = req.reject().await;
+            return None;
+        }
+
+        let offered = offer::Offer::new(name.clone(), filesize, metadata.clone());
+        let selection = match offer::accept(self.on_offer.as_ref(), self.auto_accept_size, &offered).await {
+            Ok(offer::Decision::Accept(selection)) => selection,
+            Ok(offer::Decision::Reject(reason)) => {
+                // transfer-v1 rejections carry a fixed message, so the reason
+                // stays on this side
+                match reason {
+                    Some(reason) => context.events.error(&format!("Offer rejected: {}", reason)),
+                    None => context.events.error("Offer rejected"),
+                }
+                context.events.trace(Direction::Out, "answer", serde_json::json!({ "accepted": false }));
+                let _ = req.reject().await;
+                return None;
+            }
+            Err(e) => {
+                context.events.error(&format!("Error in offer callback: {:?}", e));
+                let _ = req.reject().await;
+                return None;
+            }
+        };
+
+        let writer = match open(&offered, selection.as_ref()).await {
+            Ok(writer) => writer,
+            Err(e) => {
+                context.events.error(&e);
+                let _ = req.reject().await;
+                return None;
+            }
+        };
+        context.events.trace(Direction::Out, "answer", serde_json::json!({ "accepted": true }));
+
+        let preview = self.preview.as_ref().map(|(callback, size)| preview::Preview {
+            callback: callback.clone(),
+            size: *size,
+            filename: name.clone(),
+            filesize,
+            compressed,
+            cancel: context.cancel.clone(),
+        });
+        let writer = preview::PreviewWriter::new(writer, preview);
+        let sink = pause::Pausable::new(writer, &context.pause);
+        let sink = throttle::Throttle::new(sink, self.max_bytes_per_second);
+        let mut file = hash::HashingWriter::new(yielding::Yielding::new(sink, self.yield_budget_ms));
+        context.events.phase(Phase::TransitConnecting);
+        let watchdog = Watchdog::new(&self.timeouts);
+        let file_accept = req.accept(
+            {
+                let events = context.events.clone();
+                let stats = stats.clone();
+                let watchdog = watchdog.clone();
+                move |info| {
+                    console_log!("Connected to '{:?}'", info);
+                    let info = TransitInfo::new(&info, &relay);
+                    stats.connected(&info);
+                    watchdog.connected();
+                    events.phase(Phase::TransitConnected);
+                    events.transit(info);
+                    events.phase(Phase::Transferring);
+                }
+            },
+            {
+                let events = context.events.clone();
+                let stats = stats.clone();
+                let watchdog = watchdog.clone();
+                move |cur, total| {
+                    watchdog.activity();
+                    if let Some(progress) = stats.progress(cur, total) {
+                        events.progress(progress);
+                    }
+                }
+            },
+            &mut file,
+            futures::future::select(context.cancel.cancelled(), watchdog.clone().expiry(context.events.clone()).boxed_local()).map(|_| ()),
+        );
+
+        if let Err(e) = file_accept.await {
+            match watchdog.expired() {
+                Some(timeout) => context.events.timed_out(timeout),
+                None => context.events.error(&format!("Error in data transfer: {}", e)),
+            }
+            return None;
+        }
+
+        if let Err(e) = file.close().await {
+            context.events.error(&format!("Error writing the file: {}", e));
+            return None;
+        }
+
+        context.events.phase(Phase::Verifying);
+        let (writer, sha256) = file.finish();
+        Some(Received {
+            writer: writer.into_inner().into_inner().into_inner().into_inner(),
+            name,
+            filesize,
+            metadata,
+            selection,
+            sha256,
+            compressed,
+            stats: stats.finish(),
+        })
+    }
+
+    async fn receive_file(&self, code: String, context: &TransferContext) -> Option<ReceiveResult> {
+        let instrumented = self.instrumented(context, Direction::In);
+        let context = instrumented.as_ref().unwrap_or(context);
+        let received = self.receive_into(code, context, |offered, _| {
+            let filesize = offered.size();
+            async move {
+                // Growing the buffer as the data comes in would copy it over
+                // and over, and could still run out of memory at the very end
+                let mut buffer = Vec::new();
+                match usize::try_from(filesize) {
+                    Ok(size) if buffer.try_reserve_exact(size).is_ok() => Ok(buffer),
+                    _ => Err(format!("Not enough memory to receive {} bytes", filesize)),
+                }
+            }
+        }).await?;
+
+        let mut data = received.writer;
+        let mut sha256 = received.sha256;
+        if received.compressed {
+            data = match compression::decompress(&data, self.max_accept_size) {
+                Ok(data) => data,
+                Err(e) => {
+                    context.events.error(&format!("Error decompressing file: {}", e));
+                    return None;
+                }
+            };
+            sha256 = hash::sha256(&data);
+        }
+        console_log!("Data received, length: {}, SHA-256: {}", data.len(), sha256);
+        if !verify_sha256(context, &sha256) {
+            return None;
+        }
+        context.events.phase(Phase::Done);
+        Some(ReceiveResult {
+            filesize: data.len() as u64,
+            data,
+            filename: sanitize_filename(&received.name),
+            raw_filename: received.name,
+            metadata: received.metadata,
+            selection: received.selection,
+            sha256,
+            stats: received.stats,
+        })
+    }
+
+    /// This config without compression, for receiving into a writer that the
+    /// data can't be decompressed from.
+    fn uncompressed(&self) -> ClientConfig {
+        ClientConfig {
+            compression: false,
+            ..self.clone()
+        }
+    }
+
+    /// Receives into the `SaveTarget` that `open` creates for the sanitized
+    /// name the peer offered.
+    async fn receive_saved<F, Fut>(&self, code: String, context: &TransferContext, open: F) -> Option<SavedFile>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = Result<saved::SaveTarget, JsValue>>,
+    {
+        let instrumented = self.instrumented(context, Direction::In);
+        let context = instrumented.as_ref().unwrap_or(context);
+        let received = self.uncompressed().receive_into(code, context, |offered, _| {
+            let name = sanitize_filename(&offered.name());
+            async move { open(name).await.map_err(|e| format!("Error creating the file: {:?}", e)) }
+        }).await?;
+
+        console_log!("Data saved, length: {}, SHA-256: {}", received.filesize, received.sha256);
+        if !verify_sha256(context, &received.sha256) {
+            return None;
+        }
+        context.events.phase(Phase::Done);
+        Some(SavedFile {
+            handle: received.writer.handle,
+            raw_filename: received.name,
+            filesize: received.filesize,
+            metadata: received.metadata,
+            sha256: received.sha256,
+            stats: received.stats,
+        })
+    }
+
+    /// Sends the file open as `handle` under Node.js, see the `node` module.
+    pub(crate) async fn send_file_handle(&self, handle: NodeFileHandle, name: String, context: &TransferContext) -> Option<TransferStats> {
+        let (len, last_modified) = match node::stat(&handle).await {
+            Ok(stat) => stat,
+            Err(e) => {
+                context.events.error(&format!("Error reading the file: {:?}", e));
+                return None;
+            }
+        };
+        let metadata = FileMetadata {
+            last_modified,
+            ..FileMetadata::default()
+        };
+        let mut reader = node::FileHandleReader::new(handle, self.chunk_size);
+        console_log!("Sending {} ({} bytes)", name, len);
+
+        self.send_via_wormhole(&mut reader, len, name, &metadata, context).await
+    }
+
+    /// Receives into the file open as `handle` under Node.js.
+    pub(crate) async fn receive_to_file_handle(&self, code: String, handle: NodeFileHandle, context: &TransferContext) -> Option<TransferStats> {
+        let instrumented = self.instrumented(context, Direction::In);
+        let context = instrumented.as_ref().unwrap_or(context);
+        let received = self.uncompressed().receive_into(code, context, |_, _| async move {
+            Ok(node::FileHandleWriter::new(handle))
+        }).await?;
+
+        console_log!("Data written, length: {}, SHA-256: {}", received.filesize, received.sha256);
+        if !verify_sha256(context, &received.sha256) {
+            return None;
+        }
+        context.events.phase(Phase::Done);
+        Some(received.stats)
+    }
+
+    /// `metadata` is announced to the peer, see the `metadata` module.
+    async fn send_via_wormhole(&self, file: &mut (impl AsyncRead + Unpin), file_size: u64, file_name: String, metadata: &FileMetadata, context: &TransferContext) -> Option<TransferStats> {
+        let instrumented = self.instrumented(context, Direction::Out);
+        let context = instrumented.as_ref().unwrap_or(context);
+        let _network = network::Watch::start(context);
+        context.events.status("connecting...");
+        let metadata = FileMetadata {
+            description: self.description.clone(),
+            ..metadata.clone()
+        };
+        let send_code = match (&self.send_code, self.high_entropy_codes) {
+            (Some(code), _) => Some(code.clone()),
+            (None, true) => match code::random() {
+                Ok(code) => Some(code.to_string()),
+                Err(e) => {
+                    context.events.error(&format!("Error generating a code: {}", e));
+                    return None;
+                }
+            },
+            (None, false) => None,
+        };
+        let connector = match &send_code {
+            // Both sides claim the agreed nameplate, so the connection is
+            // only made once the peer is there
+            Some(code) => {
+                if let Some(remaining_ms) = backoff::remaining_ms() {
+                    context.events.error(&backoff_message(remaining_ms));
+                    return None;
+                }
+                context.events.phase(Phase::CodeAllocated);
+                context.events.code(&Code(code.clone()));
+                let events = context.events.clone();
+                self.claim(code, |app_versions| metadata.announce(app_versions))
+                .map(move |connected| connected.map(|(welcome, wormhole)| {
+                    if let Some(message) = &welcome {
+                        events.welcome(message);
+                    }
+                    wormhole
+                }))
+                .boxed_local()
+            }
+            None => {
+                let announced = &metadata;
+                let allocate = |words: usize| self.connect_with_failover(move |mut config| {
+                    announced.announce(&mut config.app_version);
+                    MailboxConnection::create(config, words)
+                });
+                let allocated = match self.alternate_code_length {
+                    Some(words) => futures::future::try_join(allocate(self.passphrase_component_len), allocate(words))
+                        .await
+                        .map(|(primary, alternate)| (primary, Some(alternate))),
+                    None => allocate(self.passphrase_component_len).await.map(|primary| (primary, None)),
+                };
+
+                let (mailbox, alternate) = match allocated {
+                    Ok(connected) => connected,
+                    Err(WormholeError::ServerError(e)) => {
+                        context.events.error(&format!("The rendezvous server refused the connection: {}", e));
+                        return None;
+                    }
+                    Err(e) => {
+                        context.events.error(&format!("Error in connection: {}", e));
+                        return None;
+                    }
+                };
+                console_log!("{}", mailbox.code());
+                if let Some(message) = mailbox.welcome() {
+                    context.events.welcome(message);
+                }
+                context.events.phase(Phase::CodeAllocated);
+                context.events.code(mailbox.code());
+                match alternate {
+                    // Dropping the connection that lost closes its rendezvous
+                    // server connection
+                    Some(alternate) => {
+                        context.events.alternate_code(alternate.code());
+                        futures::future::select(Box::pin(Wormhole::connect(mailbox)), Box::pin(Wormhole::connect(alternate)))
+                            .map(|either| either.factor_first().0)
+                            .boxed_local()
+                    }
+                    None => Wormhole::connect(mailbox).boxed_local(),
+                }
+            }
+        };
+
+        let connector = futures::future::select(connector, context.cancel.cancelled());
+        let expiry = match self.code_expiry_ms {
+            Some(ms) => timeout::countdown(ms, &*context.events).boxed_local(),
+            None => futures::future::pending().boxed_local(),
+        };
+        let connected = match futures::future::select(Box::pin(timer::within(self.timeouts.peer_ms, connector)), expiry).await {
+            Either::Left((Ok(connected), _)) => connected,
+            Either::Left((Err(timer::TimedOut), _)) => {
+                context.events.timed_out(Timeout::Peer);
+                return None;
+            }
+            Either::Right(_) => {
+                context.events.code_expired();
+                return None;
+            }
+        };
+        let wormhole = match connected {
+            Either::Left((Ok(wormhole), _)) => wormhole,
+            Either::Right(_) => {
+                // Dropping the connector closes the rendezvous server connection
+                context.events.error("Cancelled");
+                return None;
+            }
+            Either::Left((Err(WormholeError::PakeFailed), _)) => {
+                context.events.wrong_code();
+                return None;
+            }
+            Either::Left((Err(e), _)) => {
+                context.events.error(&format!("Error waiting for connection: {}", e));
+                return None;
+            }
+        };
+        let (wormhole, relay_hints, relay_url) = match self.establish(wormhole, context).await {
+            Some(established) => established,
+            None => return None,
+        };
+
+        let mut compressed;
+        let compress = self.compression && compression::negotiated(wormhole.peer_version());
+        let (file, file_size): (&mut (dyn AsyncRead + Unpin), u64) = if compress {
+            let mut data = Vec::new();
+            let packed = match file.read_to_end(&mut data).await {
+                Ok(_) => compression::compress(&data),
+                Err(e) => Err(e),
+            };
+            compressed = match packed {
+                Ok(packed) => futures::io::Cursor::new(packed),
+                Err(e) => {
+                    context.events.error(&format!("Error compressing file: {}", e));
+                    let _ = wormhole.close().await;
+                    return None;
+                }
+            };
+            console_log!("Compressed {} bytes to {}", file_size, compressed.get_ref().len());
+            let len = compressed.get_ref().len() as u64;
+            (&mut compressed, len)
+        } else {
+            (file, file_size)
+        };
+
+        context.events.trace(Direction::Out, "offer", serde_json::json!({
+            "filename": file_name,
+            "filesize": file_size,
+            "compressed": compress,
+        }));
+        let file = throttle::Throttle::new(pause::Pausable::new(file, &context.pause), self.max_bytes_per_second);
+        let mut file = js::AssumeSend(yielding::Yielding::new(file, self.yield_budget_ms));
+        let layout = metadata.entries.as_deref().and_then(archive::Layout::new);
+        let stats = Rc::new(StatsRecorder::new(&relay_url, self.progress_interval, layout));
+        let relay = relay_url.to_string();
+        context.events.phase(Phase::TransitConnecting);
+        let watchdog = Watchdog::new(&self.timeouts);
+        let transfer_result = transfer::send_file(
+            wormhole,
+            relay_hints,
+            &mut file,
+            file_name,
+            file_size,
+            self.abilities,
+            {
+                let events = context.events.clone();
+                let stats = stats.clone();
+                let watchdog = watchdog.clone();
+                move |info| {
+                    console_log!("Connected to '{:?}'", info);
+                    let info = TransitInfo::new(&info, &relay);
+                    stats.connected(&info);
+                    watchdog.connected();
+                    events.phase(Phase::TransitConnected);
+                    events.transit(info);
+                    events.phase(Phase::Transferring);
+                }
+            },
+            {
+                let events = context.events.clone();
+                let stats = stats.clone();
+                let watchdog = watchdog.clone();
+                move |cur, total| {
+                    watchdog.activity();
+                    if let Some(progress) = stats.progress(cur, total) {
+                        events.progress(progress);
+                    }
+                }
+            },
+            futures::future::select(context.cancel.cancelled(), watchdog.clone().expiry(context.events.clone()).boxed_local()).map(|_| ()),
+        ).await;
+
+        if let (Err(_), Some(timeout)) = (&transfer_result, watchdog.expired()) {
+            context.events.timed_out(timeout);
+            return None;
+        }
+        match transfer_result {
+            Ok(_) => {
+                console_log!("Data sent");
+                context.events.phase(Phase::Done);
+                Some(stats.finish())
+            }
+            Err(TransferError::PeerError(message)) => {
+                context.events.rejected(&message);
+                None
+            }
+            Err(e) => {
+                context.events.error(&format!("Error in data transfer: {}", e));
+                None
+            }
+        }
+    }
+}
+
+/// What `ClientConfig::receive_into` received.
+struct Received<W> {
+    writer: W,
+    /// The file name and size as offered by the peer.
+    name: String,
+    filesize: u64,
+    /// What the peer announced beyond name and size.
+    metadata: FileMetadata,
+    /// The archive entries picked by the offer callback, if it did.
+    selection: Option<offer::Selection>,
+    /// Of the data as written, which is still compressed if `compressed`.
+    sha256: String,
+    compressed: bool,
+    stats: TransferStats,
 }
 
-// async fn send_via_wormhole(file: Vec<u8>, file_size: u64, file_name: String, output: &web_sys::HtmlElement) {
-//     let connect = Wormhole::connect_without_code(
-//         transfer::APP_CONFIG.rendezvous_url("ws://relay.magic-wormhole.io:4000/v1".into()),
-//         2,
-//     );
-
-//     match connect.await {
-//         Ok((server_welcome, connector)) => {
-//             console_log!("{}", server_welcome.code);
-//             output.set_inner_text(&format!("wormhole code:  {}", server_welcome.code));
-
-//             match connector.await {
-//                 Ok(wormhole) => {
-//                     let transfer_result = transfer::send_file(
-//                         wormhole,
-//                         url::Url::parse("ws://piegames.de:4002").unwrap(),
-//                         &mut &file[..],
-//                         PathBuf::from(file_name),
-//                         file_size,
-//                         transit::Abilities::FORCE_RELAY,
-//                         |info, address| {
-//                             console_log!("Connected to '{:?}' on address {:?}", info, address);
-//                         },
-//                         |cur, total| {
-//                             console_log!("Progress: {}/{}", cur, total);
-//                         },
-//                         NoOpFuture {},
-//                     ).await;
-
-//                     match transfer_result {
-//                         Ok(_) => {
-//                             console_log!("Data sent");
-//                         }
-//                         Err(e) => {
-//                             console_log!("Error in data transfer: {:?}", e);
-//                         }
-//                     }
-//                 }
-//                 Err(_) => {
-//                     console_log!("Error waiting for connection");
-//                 }
-//             }
-//         }
-//         Err(_) => {
-//             console_log!("Error in connection");
-//         }
-//     };
-// }
-
-// #[derive(serde::Serialize, serde::Deserialize)]
-// pub struct ReceiveResult {
-//     data: Vec<u8>,
-//     filename: String,
-//     filesize: u64,
-// }
-
-// #[wasm_bindgen]
-// pub async fn receive(code: String, output: web_sys::HtmlElement) -> Option<JsValue> {
-//     let connect = Wormhole::connect_with_code(
-//         transfer::APP_CONFIG.rendezvous_url("ws://relay.magic-wormhole.io:4000/v1".into()),
-//         Code(code),
-//     );
-
-//     return match connect.await {
-//         Ok((_, wormhole)) => {
-//             let req = transfer::request_file(
-//                 wormhole,
-//                 url::Url::parse("ws://piegames.de:4002").unwrap(),
-//                 transit::Abilities::FORCE_RELAY,
-//                 NoOpFuture {},
-//             ).await;
-
-//             let mut file: Vec<u8> = Vec::new();
-
-//             match req {
-//                 Ok(Some(req)) => {
-//                     let filename = req.filename.clone();
-//                     let filesize = req.filesize;
-//                     console_log!("File name: {:?}, size: {}", filename, filesize);
-//                     let file_accept = req.accept(
-//                         |info, address| {
-//                             console_log!("Connected to '{:?}' on address {:?}", info, address);
-//                         },
-//                         |cur, total| {
-//                             console_log!("Progress: {}/{}", cur, total);
-//                         },
-//                         &mut file,
-//                         NoOpFuture {},
-//                     );
-
-//                     match file_accept.await {
-//                         Ok(_) => {
-//                             console_log!("Data received, length: {}", file.len());
-//                             //let array: js_sys::Array = file.into_iter().map(JsValue::from).collect();
-//                             //data: js_sys::Uint8Array::new(&array),
-//                             let result = ReceiveResult {
-//                                 data: file,
-//                                 filename: filename.to_str().unwrap_or_default().into(),
-//                                 filesize,
-//                             };
-//                             return Some(JsValue::from_serde(&result).unwrap());
-//                         }
-//                         Err(e) => {
-//                             console_log!("Error in data transfer: {:?}", e);
-//                             None
-//                         }
-//                     }
-//                 }
-//                 _ => {
-//                     console_log!("No ReceiveRequest");
-//                     None
-//                 }
-//             }
-//         }
-//         Err(_) => {
-//             output.set_inner_text("Error in connection");
-//             None
-//         }
-//     };
-// }
+/// The error shown while claiming is held back after a wrong code.
+fn backoff_message(remaining_ms: f64) -> String {
+    format!("A wrong code was used, try again in {} seconds", (remaining_ms / 1000.0).ceil())
+}
+
+/// Whether `sha256` is what the transfer expects, reporting a mismatch.
+fn verify_sha256(context: &TransferContext, sha256: &str) -> bool {
+    match &context.expected_sha256 {
+        Some(expected) if !expected.eq_ignore_ascii_case(sha256) => {
+            context.events.error(&format!("The received data does not match the expected SHA-256 {}", expected));
+            false
+        }
+        _ => true,
+    }
+}
+
+/// AppIDs namespace the mailboxes on a rendezvous server, e.g.
+/// `lothar.com/wormhole/text-or-file-xfer`.
+fn parse_appid(appid: &str) -> Result<AppID, JsValue> {
+    let appid = appid.trim();
+    if appid.is_empty() {
+        return Err(JsValue::from_str("The AppID must not be empty"));
+    }
+    if appid.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(JsValue::from_str(&format!("Invalid AppID: {:?}", appid)));
+    }
+    Ok(AppID::from(appid.to_owned()))
+}
@@ -0,0 +1,141 @@
+//! The logger installed by `init_with_options`: a level, per-module
+//! levels, and either the browser console or a JS callback as the sink.
+
+use std::cell::{Cell, RefCell};
+use std::str::FromStr;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::LogCallback;
+
+#[wasm_bindgen]
+extern {
+    #[wasm_bindgen(js_namespace = console, js_name = error)]
+    fn console_error(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = warn)]
+    fn console_warn(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = info)]
+    fn console_info(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = debug)]
+    fn console_debug(s: &str);
+}
+
+struct Options {
+    level: LevelFilter,
+    /// Module path prefixes with their own level, most specific first.
+    filters: Vec<(String, LevelFilter)>,
+    sink: Option<js_sys::Function>,
+}
+
+impl Options {
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.filters
+            .iter()
+            .find(|(module, _)| {
+                target == module || (target.starts_with(module.as_str()) && target[module.len()..].starts_with("::"))
+            })
+            .map_or(self.level, |(_, level)| *level)
+    }
+
+    /// The most verbose level any module logs at.
+    fn max_level(&self) -> LevelFilter {
+        self.filters.iter().map(|(_, level)| *level).fold(self.level, Ord::max)
+    }
+}
+
+thread_local! {
+    // wasm is single threaded, the callback can't live in the `Sync` logger.
+    static OPTIONS: RefCell<Option<Options>> = const { RefCell::new(None) };
+    static INSTALLED: Cell<bool> = const { Cell::new(false) };
+}
+
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        OPTIONS.with(|options| {
+            options
+                .borrow()
+                .as_ref()
+                .is_some_and(|options| metadata.level() <= options.level_for(metadata.target()))
+        })
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        let sink = OPTIONS.with(|options| options.borrow().as_ref().and_then(|options| options.sink.clone()));
+        match sink {
+            Some(sink) => {
+                let _ = sink.call3(
+                    &JsValue::NULL,
+                    &JsValue::from_str(record.level().as_str()),
+                    &JsValue::from_str(record.target()),
+                    &JsValue::from_str(&message),
+                );
+            }
+            None => {
+                let line = format!("{} {}: {}", record.level(), record.target(), message);
+                match record.level() {
+                    Level::Error => console_error(&line),
+                    Level::Warn => console_warn(&line),
+                    Level::Info => console_info(&line),
+                    Level::Debug | Level::Trace => console_debug(&line),
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter, JsValue> {
+    LevelFilter::from_str(level.trim())
+        .map_err(|_| JsValue::from_str(&format!("Invalid log level {:?}, expected off, error, warn, info, debug or trace", level)))
+}
+
+/// Parses `module=level` entries, like `magic_wormhole::core=warn`.
+fn parse_filters(filters: &js_sys::Array) -> Result<Vec<(String, LevelFilter)>, JsValue> {
+    let mut parsed = filters
+        .iter()
+        .map(|filter| {
+            let filter = filter
+                .as_string()
+                .ok_or_else(|| JsValue::from_str("Log filters must be strings"))?;
+            match filter.split_once('=') {
+                Some((module, level)) if !module.trim().is_empty() => Ok((module.trim().to_owned(), parse_level(level)?)),
+                _ => Err(JsValue::from_str(&format!("Invalid log filter {:?}, expected module=level", filter))),
+            }
+        })
+        .collect::<Result<Vec<_>, JsValue>>()?;
+    parsed.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+    Ok(parsed)
+}
+
+pub(crate) fn install(level: &str, filters: Option<js_sys::Array>, sink: Option<LogCallback>) -> Result<(), JsValue> {
+    let options = Options {
+        level: parse_level(level)?,
+        filters: match filters {
+            Some(filters) => parse_filters(&filters)?,
+            None => Vec::new(),
+        },
+        sink: sink.map(JsCast::unchecked_into),
+    };
+
+    if !INSTALLED.with(Cell::get) {
+        log::set_logger(&LOGGER).map_err(|_| JsValue::from_str("Another logger is already installed, was init() called?"))?;
+        INSTALLED.with(|installed| installed.set(true));
+    }
+    log::set_max_level(options.max_level());
+    OPTIONS.with(|current| *current.borrow_mut() = Some(options));
+    Ok(())
+}
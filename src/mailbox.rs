@@ -0,0 +1,84 @@
+use futures::lock::Mutex;
+use magic_wormhole::{Wormhole, WormholeError};
+use wasm_bindgen::prelude::*;
+
+use crate::js;
+use crate::verifier;
+
+/// The encrypted channel of a wormhole, for protocols other than file
+/// transfer. Both sides have to use the same AppID.
+///
+/// Messages are delivered in order. magic-wormhole numbers the mailbox
+/// phases itself, so messages carry no phase name of their own. Calls are
+/// served one at a time: a `send_json` issued while `receive_json` waits
+/// only runs once a message has arrived.
+#[wasm_bindgen]
+pub struct Mailbox {
+    wormhole: Mutex<Option<Wormhole>>,
+}
+
+impl Mailbox {
+    /// Lets `on_verifier` confirm the session before handing out the mailbox.
+    pub(crate) async fn confirm(wormhole: Wormhole, on_verifier: Option<&js_sys::Function>) -> Result<Self, JsValue> {
+        match verifier::confirm(&wormhole, on_verifier).await {
+            Ok(true) => Ok(Mailbox {
+                wormhole: Mutex::new(Some(wormhole)),
+            }),
+            Ok(false) => {
+                let _ = wormhole.close().await;
+                Err(JsValue::from_str("Verification rejected"))
+            }
+            Err(e) => {
+                let _ = wormhole.close().await;
+                Err(e)
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Mailbox {
+    /// Sends `payload`, which has to be serializable as JSON.
+    pub async fn send_json(&self, payload: JsValue) -> Result<(), JsValue> {
+        let payload: serde_json::Value = js::from_js(payload)
+            .map_err(|e| JsValue::from_str(&format!("Payload is not JSON: {}", e)))?;
+        let mut wormhole = self.wormhole.lock().await;
+        let wormhole = wormhole.as_mut().ok_or_else(closed)?;
+        wormhole
+            .send_json(&payload)
+            .await
+            .map_err(|e| JsValue::from_str(&format!("Error sending message: {}", e)))
+    }
+
+    /// Waits for the next message from the peer.
+    pub async fn receive_json(&self) -> Result<JsValue, JsValue> {
+        let mut wormhole = self.wormhole.lock().await;
+        let wormhole = wormhole.as_mut().ok_or_else(closed)?;
+        let payload: serde_json::Value = wormhole
+            .receive_json()
+            .await
+            .map_err(|e| JsValue::from_str(&format!("Error receiving message: {}", e)))?
+            .map_err(|e| JsValue::from_str(&format!("Received message is not JSON: {}", e)))?;
+        Ok(js::to_js(&payload)?)
+    }
+
+    /// Releases the mailbox on the rendezvous server. Later calls fail.
+    pub async fn close(&self) -> Result<(), JsValue> {
+        let wormhole = self.wormhole.lock().await.take().ok_or_else(closed)?;
+        wormhole
+            .close()
+            .await
+            .map_err(|e| JsValue::from_str(&format!("Error closing mailbox: {}", e)))
+    }
+}
+
+pub(crate) fn connection_error(e: WormholeError) -> JsValue {
+    match e {
+        WormholeError::PakeFailed => JsValue::from_str("Wrong code"),
+        e => JsValue::from_str(&format!("Error in connection: {}", e)),
+    }
+}
+
+fn closed() -> JsValue {
+    JsValue::from_str("Mailbox is closed")
+}
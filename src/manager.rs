@@ -0,0 +1,93 @@
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::cancel::Cancel;
+use crate::pause::Pause;
+use crate::{ClientConfig, WormholeSession};
+
+/// The controls of the sessions created by a `TransferManager`, by id.
+#[derive(Default)]
+pub(crate) struct Registry {
+    next_id: Cell<u32>,
+    sessions: RefCell<BTreeMap<u32, (Cancel, Pause)>>,
+}
+
+impl Registry {
+    pub(crate) fn register(&self, cancel: &Cancel, pause: &Pause) -> u32 {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.sessions.borrow_mut().insert(id, (cancel.clone(), pause.clone()));
+        id
+    }
+
+    pub(crate) fn unregister(&self, id: u32) {
+        self.sessions.borrow_mut().remove(&id);
+    }
+}
+
+/// Creates sessions that run side by side, each with its own listeners,
+/// and keeps track of them by id so they can be controlled from one place.
+/// A session leaves the manager when it is freed.
+#[wasm_bindgen]
+pub struct TransferManager {
+    config: ClientConfig,
+    registry: Rc<Registry>,
+}
+
+#[wasm_bindgen]
+impl TransferManager {
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: &ClientConfig) -> TransferManager {
+        TransferManager {
+            config: config.clone(),
+            registry: Rc::new(Registry::default()),
+        }
+    }
+
+    /// A new session, see `WormholeSession`. Its `id` identifies it here.
+    #[wasm_bindgen(js_name = createTransfer)]
+    pub fn create_transfer(&self) -> WormholeSession {
+        WormholeSession::registered(&self.config, &self.registry)
+    }
+
+    /// The ids of the sessions that have not been freed yet.
+    pub fn ids(&self) -> Vec<u32> {
+        self.registry.sessions.borrow().keys().copied().collect()
+    }
+
+    /// Closes the session with `id`, see `WormholeSession.close`. Returns
+    /// whether there was such a session.
+    pub fn close(&self, id: u32) -> bool {
+        self.with_session(id, |cancel, _| cancel.cancel())
+    }
+
+    pub fn pause(&self, id: u32) -> bool {
+        self.with_session(id, |_, pause| pause.set_paused(true))
+    }
+
+    pub fn resume(&self, id: u32) -> bool {
+        self.with_session(id, |_, pause| pause.set_paused(false))
+    }
+
+    #[wasm_bindgen(js_name = closeAll)]
+    pub fn close_all(&self) {
+        for (cancel, _) in self.registry.sessions.borrow().values() {
+            cancel.cancel();
+        }
+    }
+}
+
+impl TransferManager {
+    fn with_session(&self, id: u32, f: impl FnOnce(&Cancel, &Pause)) -> bool {
+        match self.registry.sessions.borrow().get(&id) {
+            Some((cancel, pause)) => {
+                f(cancel, pause);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! File metadata the transfer-v1 offer has no room for.
+//!
+//! The sender announces `{"magic-wormhole-wasm": {"file": {"mime_type":
+//! "image/png", "last_modified": 1650000000000, "description": "invoices
+//! for March", "entries": [{"name": "march/1.pdf", "size": 48213}]}}}` in
+//! its app versions, which are exchanged, encrypted, before the offer. All
+//! fields are optional, `last_modified` is in milliseconds since the epoch
+//! like `File.lastModified`, and `entries` lists the files of an archive
+//! sent with `send_files` or `send_directory`. Other clients ignore it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::archive::Layout;
+use crate::APP_VERSION_KEY;
+
+/// The longest description accepted, in bytes. Longer ones from the peer are
+/// cut off.
+pub(crate) const MAX_DESCRIPTION_LEN: usize = 1024;
+
+/// The most archive entries accepted from the peer; the rest are dropped.
+pub(crate) const MAX_ENTRIES: usize = 10_000;
+
+/// A file in an offered archive.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct EntryMetadata {
+    pub(crate) name: String,
+    pub(crate) size: u64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub(crate) struct FileMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) mime_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) last_modified: Option<f64>,
+    /// A note from the sender about the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) entries: Option<Vec<EntryMetadata>>,
+}
+
+impl FileMetadata {
+    pub(crate) fn of_file(file: &web_sys::File) -> Self {
+        FileMetadata {
+            last_modified: Some(file.last_modified()),
+            ..Self::of_blob(file)
+        }
+    }
+
+    pub(crate) fn of_blob(blob: &web_sys::Blob) -> Self {
+        let mime_type = blob.type_();
+        FileMetadata {
+            mime_type: Some(mime_type).filter(|mime_type| !mime_type.is_empty()),
+            ..Self::default()
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.mime_type.is_none() && self.last_modified.is_none() && self.description.is_none() && self.entries.is_none()
+    }
+
+    /// Adds the metadata to our app versions.
+    pub(crate) fn announce(&self, app_versions: &mut serde_json::Value) {
+        if self.is_empty() {
+            return;
+        }
+        if let Some(fields) = crate::own_app_version(app_versions) {
+            fields.insert("file".to_owned(), serde_json::to_value(self).unwrap());
+        }
+    }
+
+    /// The metadata the peer announced, if any. `archive_size` is the
+    /// offered size, unless the transfer is compressed; entries that do not
+    /// add up to it, or to any size at all, are dropped.
+    pub(crate) fn from_peer(peer_version: &serde_json::Value, archive_size: Option<u64>) -> Self {
+        let mut metadata: Self = peer_version
+            .pointer(&format!("/{}/file", APP_VERSION_KEY))
+            .and_then(|file| serde_json::from_value(file.clone()).ok())
+            .unwrap_or_default();
+        if let Some(description) = &mut metadata.description {
+            let mut len = description.len().min(MAX_DESCRIPTION_LEN);
+            while !description.is_char_boundary(len) {
+                len -= 1;
+            }
+            description.truncate(len);
+        }
+        let adds_up = |entries: &[EntryMetadata]| {
+            Layout::new(entries).is_some_and(|layout| archive_size.is_none_or(|size| size == layout.size()))
+        };
+        if !metadata.entries.as_deref().is_none_or(adds_up) {
+            metadata.entries = None;
+        }
+        if let Some(entries) = &mut metadata.entries {
+            entries.truncate(MAX_ENTRIES);
+        }
+        metadata
+    }
+}
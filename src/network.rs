@@ -0,0 +1,69 @@
+//! Holding a transfer while the browser is offline, e.g. while a phone
+//! switches from Wi-Fi to cellular.
+//!
+//! Data is neither read nor written between the `offline` and `online`
+//! events, so nothing piles up in the WebSocket buffers while they cannot
+//! drain. Whether the transfer survives depends on its connections: after a
+//! short outage the rendezvous and transit WebSockets are usually still
+//! open, but once the browser closed one of them the transfer fails.
+//! magic-wormhole cannot reconnect a running transfer, and transfer-v1
+//! cannot resume one, so it then has to be started over.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::context::TransferContext;
+
+/// Watches the connectivity of the browser for as long as it lives.
+pub(crate) struct Watch {
+    target: web_sys::EventTarget,
+    offline: Closure<dyn FnMut()>,
+    online: Closure<dyn FnMut()>,
+}
+
+impl Watch {
+    /// `None` where there are no such events, as in Node.js.
+    pub(crate) fn start(context: &TransferContext) -> Option<Self> {
+        let global = js_sys::global();
+        let add_event_listener = js_sys::Reflect::get(&global, &JsValue::from_str("addEventListener")).ok()?;
+        if !add_event_listener.is_function() {
+            return None;
+        }
+        // Only a pause of our own is lifted when the network is back, not
+        // one the application asked for.
+        let paused_here = Rc::new(Cell::new(false));
+        let offline = {
+            let (events, pause, paused_here) = (context.events.clone(), context.pause.clone(), paused_here.clone());
+            Closure::wrap(Box::new(move || {
+                if !pause.is_paused() {
+                    pause.set_paused(true);
+                    paused_here.set(true);
+                }
+                events.network(false);
+            }) as Box<dyn FnMut()>)
+        };
+        let online = {
+            let (events, pause) = (context.events.clone(), context.pause.clone());
+            Closure::wrap(Box::new(move || {
+                if paused_here.replace(false) {
+                    pause.set_paused(false);
+                }
+                events.network(true);
+            }) as Box<dyn FnMut()>)
+        };
+        let target: web_sys::EventTarget = global.unchecked_into();
+        target.add_event_listener_with_callback("offline", offline.as_ref().unchecked_ref()).ok()?;
+        target.add_event_listener_with_callback("online", online.as_ref().unchecked_ref()).ok()?;
+        Some(Watch { target, offline, online })
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        let _ = self.target.remove_event_listener_with_callback("offline", self.offline.as_ref().unchecked_ref());
+        let _ = self.target.remove_event_listener_with_callback("online", self.online.as_ref().unchecked_ref());
+    }
+}
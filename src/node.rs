@@ -0,0 +1,157 @@
+//! Files on disk under Node.js (and Electron), as opened with
+//! `fs.promises.open()`.
+//!
+//! The rest of the crate works under wasm-pack's `nodejs` target as long as
+//! a `WebSocket` global exists: Node 22 has one, older versions can install
+//! the `ws` module's with `globalThis.WebSocket = require("ws")`. Only the
+//! file I/O needs the bindings here, which take the `FileHandle` from JS so
+//! that browser builds never import `fs`.
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::{AsyncRead, AsyncWrite};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::js::js_error;
+
+#[wasm_bindgen]
+extern {
+    /// A `FileHandle` from `fs.promises.open()`.
+    pub type NodeFileHandle;
+
+    #[wasm_bindgen(method)]
+    fn read(this: &NodeFileHandle, buffer: &js_sys::Uint8Array, offset: f64, length: f64, position: f64) -> js_sys::Promise;
+
+    #[wasm_bindgen(method)]
+    fn write(this: &NodeFileHandle, buffer: &js_sys::Uint8Array, offset: f64, length: f64, position: f64) -> js_sys::Promise;
+
+    #[wasm_bindgen(method)]
+    fn stat(this: &NodeFileHandle) -> js_sys::Promise;
+}
+
+/// `field` of the object a `FileHandle` promise resolved to, as a number.
+fn number(value: &JsValue, field: &str) -> io::Result<f64> {
+    js_sys::Reflect::get(value, &JsValue::from_str(field))
+        .map_err(js_error)?
+        .as_f64()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("No {} in {:?}", field, value)))
+}
+
+/// The size of the file and when it was last modified, in milliseconds since
+/// the epoch, from `stat()`.
+pub(crate) async fn stat(handle: &NodeFileHandle) -> Result<(u64, Option<f64>), JsValue> {
+    let stats = JsFuture::from(handle.stat()).await?;
+    let size = number(&stats, "size").map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok((size as u64, number(&stats, "mtimeMs").ok()))
+}
+
+/// Reads a `NodeFileHandle` from its start, one chunk at a time.
+pub(crate) struct FileHandleReader {
+    handle: NodeFileHandle,
+    buffer: js_sys::Uint8Array,
+    position: u64,
+    pending: Option<JsFuture>,
+    /// The last chunk read, of which `chunk[chunk_offset..]` is not consumed yet.
+    chunk: Vec<u8>,
+    chunk_offset: usize,
+}
+
+impl FileHandleReader {
+    pub(crate) fn new(handle: NodeFileHandle, chunk_size: usize) -> Self {
+        FileHandleReader {
+            handle,
+            buffer: js_sys::Uint8Array::new_with_length(chunk_size.max(1) as u32),
+            position: 0,
+            pending: None,
+            chunk: Vec::new(),
+            chunk_offset: 0,
+        }
+    }
+}
+
+impl AsyncRead for FileHandleReader {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        if self.chunk_offset == self.chunk.len() {
+            if self.pending.is_none() {
+                let promise = self.handle.read(&self.buffer, 0.0, self.buffer.length() as f64, self.position as f64);
+                self.pending = Some(JsFuture::from(promise));
+            }
+            let result = match Pin::new(self.pending.as_mut().unwrap()).poll(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(result) => result,
+            };
+            self.pending = None;
+            let read = number(&result.map_err(js_error)?, "bytesRead")? as usize;
+            if read == 0 {
+                return Poll::Ready(Ok(0));
+            }
+            let this = &mut *self;
+            this.chunk.resize(read, 0);
+            this.buffer.subarray(0, read as u32).copy_to(&mut this.chunk);
+            this.chunk_offset = 0;
+            this.position += read as u64;
+        }
+        let len = buf.len().min(self.chunk.len() - self.chunk_offset);
+        buf[..len].copy_from_slice(&self.chunk[self.chunk_offset..self.chunk_offset + len]);
+        self.chunk_offset += len;
+        Poll::Ready(Ok(len))
+    }
+}
+
+/// Writes to a `NodeFileHandle` from its start, one chunk behind: a write
+/// completes as soon as the previous one has. The handle stays open,
+/// closing it is up to its owner.
+pub(crate) struct FileHandleWriter {
+    handle: NodeFileHandle,
+    position: u64,
+    pending: Option<(JsFuture, usize)>,
+}
+
+impl FileHandleWriter {
+    pub(crate) fn new(handle: NodeFileHandle) -> Self {
+        FileHandleWriter {
+            handle,
+            position: 0,
+            pending: None,
+        }
+    }
+}
+
+impl AsyncWrite for FileHandleWriter {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        if self.as_mut().poll_flush(cx)?.is_pending() {
+            return Poll::Pending;
+        }
+        let data = js_sys::Uint8Array::from(buf);
+        let promise = self.handle.write(&data, 0.0, buf.len() as f64, self.position as f64);
+        self.pending = Some((JsFuture::from(promise), buf.len()));
+        self.position += buf.len() as u64;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let (pending, len) = match self.pending.as_mut() {
+            Some((pending, len)) => (pending, *len),
+            None => return Poll::Ready(Ok(())),
+        };
+        let result = match Pin::new(pending).poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(result) => result,
+        };
+        self.pending = None;
+        let written = number(&result.map_err(js_error)?, "bytesWritten")? as usize;
+        if written != len {
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::WriteZero, format!("Only {} of {} bytes were written", written, len))));
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
@@ -0,0 +1,174 @@
+//! Deciding on an offered file, see `ClientConfig.set_offer_callback`.
+//!
+//! transfer-v1 offers a single file, so files and directories are sent as
+//! one zip archive, whose entries the sender announces (see `metadata`).
+//! The receiver gets them as an `Offer` and may pick some; the archive is
+//! still transferred whole, but only the picked entries are unpacked.
+
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::js;
+use crate::metadata::{EntryMetadata, FileMetadata};
+use crate::size;
+use crate::typescript::OfferEntries;
+
+pub(crate) enum Decision {
+    /// Accepted, with the entries picked from an archive, if any were.
+    Accept(Option<Selection>),
+    /// Rejected, with the reason given by the offer callback, if any.
+    Reject(Option<String>),
+}
+
+/// What the peer offers.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct Offer {
+    name: String,
+    size: u64,
+    metadata: FileMetadata,
+}
+
+/// An entry of `Offer.entries`.
+#[derive(Serialize)]
+struct OfferEntry<'a> {
+    name: &'a str,
+    size: u64,
+    /// `"file"` or `"directory"`.
+    kind: &'static str,
+}
+
+impl Offer {
+    pub(crate) fn new(name: String, size: u64, metadata: FileMetadata) -> Self {
+        Offer { name, size, metadata }
+    }
+
+    /// An offer of an archive of `entries`, given by path and size, as the
+    /// sender would announce it. For Rust code that hands offers to an
+    /// offer callback itself.
+    pub fn archive(name: String, size: u64, entries: Vec<(String, u64)>) -> Self {
+        let entries = entries.into_iter().map(|(name, size)| EntryMetadata { name, size }).collect();
+        let metadata = FileMetadata { entries: Some(entries), ..FileMetadata::default() };
+        Offer::new(name, size, metadata)
+    }
+
+    pub(crate) fn metadata(&self) -> &FileMetadata {
+        &self.metadata
+    }
+}
+
+#[wasm_bindgen]
+impl Offer {
+    /// The file name as offered, not sanitized.
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn description(&self) -> Option<String> {
+        self.metadata.description.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn mime_type(&self) -> Option<String> {
+        self.metadata.mime_type.clone()
+    }
+
+    /// Whether the sender announced the file as an archive of `entries`.
+    #[wasm_bindgen(getter)]
+    pub fn is_archive(&self) -> bool {
+        self.metadata.entries.is_some()
+    }
+
+    /// The entries of an archive as `{name, size, kind}` objects, `kind`
+    /// being `"file"` or `"directory"`, with the directories implied by the
+    /// file paths listed before their contents. `size` is a `BigInt`, like
+    /// `size`. Empty if this is a single file.
+    #[wasm_bindgen(getter)]
+    pub fn entries(&self) -> Result<OfferEntries, JsValue> {
+        let files = self.metadata.entries.as_deref().unwrap_or_default();
+        let mut directories = BTreeSet::new();
+        let mut entries = Vec::new();
+        for file in files {
+            let mut end = 0;
+            while let Some(slash) = file.name[end..].find('/') {
+                end += slash + 1;
+                let directory = &file.name[..end - 1];
+                if !directory.is_empty() && directories.insert(directory) {
+                    entries.push(OfferEntry { name: directory, size: 0, kind: "directory" });
+                }
+            }
+            entries.push(OfferEntry { name: &file.name, size: file.size, kind: "file" });
+        }
+        Ok(js::to_js_with_bigints(&entries)?.unchecked_into())
+    }
+}
+
+/// The archive entries the receiver picked. A directory stands for all
+/// entries below it.
+#[derive(Clone, Debug)]
+pub struct Selection(Vec<String>);
+
+impl Selection {
+    /// From an array of entry names.
+    pub(crate) fn from_js(names: &js_sys::Array) -> Result<Self, JsValue> {
+        names
+            .iter()
+            .map(|name| name.as_string().ok_or_else(|| JsValue::from_str("Picked entries must be names")))
+            .collect::<Result<_, _>>()
+            .map(Selection)
+    }
+
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.0.iter().any(|picked| {
+            let picked = picked.trim_end_matches('/');
+            name == picked || (name.starts_with(picked) && name[picked.len()..].starts_with('/'))
+        })
+    }
+}
+
+/// Decides whether to accept `offer`. Offers of up to `auto_accept_size`
+/// bytes are accepted right away; all others are passed to `callback`,
+/// which may return a promise. It rejects the offer by returning `false`
+/// or a string with the reason, and picks entries of an archive by
+/// returning their names. Without a callback, larger offers are rejected
+/// when an auto-accept size is set and accepted otherwise.
+pub(crate) async fn accept(callback: Option<&js_sys::Function>, auto_accept_size: Option<u64>, offer: &Offer) -> Result<Decision, JsValue> {
+    if auto_accept_size.is_some_and(|max| offer.size <= max) {
+        return Ok(Decision::Accept(None));
+    }
+    let callback = match callback {
+        Some(callback) => callback,
+        None if auto_accept_size.is_none() => return Ok(Decision::Accept(None)),
+        None => return Ok(Decision::Reject(None)),
+    };
+
+    let args = js_sys::Array::of4(
+        &JsValue::from_str(&offer.name),
+        &size::declared(offer.size),
+        &JsValue::from(offer.metadata.description.as_deref()),
+        &offer.clone().into(),
+    );
+    let mut result = callback.apply(&JsValue::NULL, &args)?;
+    if let Some(promise) = result.dyn_ref::<js_sys::Promise>() {
+        result = wasm_bindgen_futures::JsFuture::from(promise.clone()).await?;
+    }
+
+    if let Some(picked) = result.dyn_ref::<js_sys::Array>() {
+        return Ok(Decision::Accept(Some(Selection::from_js(picked)?)));
+    }
+    Ok(match result.as_string() {
+        Some(reason) => Decision::Reject(Some(reason)),
+        None if result == JsValue::FALSE => Decision::Reject(None),
+        None => Decision::Accept(None),
+    })
+}
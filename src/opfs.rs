@@ -0,0 +1,48 @@
+//! Files in the Origin Private File System, from `navigator.storage`.
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::directory::{self, FileSystemDirectoryHandle, FileSystemFileHandle};
+use crate::runtime::Runtime;
+use crate::saved::SaveTarget;
+use crate::stream::{StreamWriter, WritableStream};
+use crate::writer::SyncAccessWriter;
+
+#[wasm_bindgen]
+extern {
+    type StorageManager;
+
+    #[wasm_bindgen(method, js_name = getDirectory)]
+    fn get_directory(this: &StorageManager) -> js_sys::Promise;
+}
+
+/// The root directory of the origin's private file system.
+async fn root() -> Result<FileSystemDirectoryHandle, JsValue> {
+    let navigator = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("navigator"))?;
+    let storage = js_sys::Reflect::get(&navigator, &JsValue::from_str("storage"))?;
+    if storage.is_undefined() {
+        return Err(JsValue::from_str("The Origin Private File System is not available"));
+    }
+    Ok(JsFuture::from(storage.unchecked_into::<StorageManager>().get_directory()).await?.unchecked_into())
+}
+
+/// Creates or truncates the file `name` in the root directory. Dedicated
+/// workers write through a sync access handle, which is faster and the
+/// only way Safari supports, pages through a writable stream.
+pub(crate) async fn create(name: &str) -> Result<SaveTarget, JsValue> {
+    let handle: FileSystemFileHandle = JsFuture::from(root().await?.get_file_handle(name, &directory::create_options()?))
+        .await?
+        .unchecked_into();
+
+    let in_worker = Runtime::detect() == Runtime::Worker;
+    let writer: Box<dyn futures::io::AsyncWrite + Unpin> =
+        if in_worker && js_sys::Reflect::has(&handle, &JsValue::from_str("createSyncAccessHandle"))? {
+            let access = JsFuture::from(handle.create_sync_access_handle()).await?;
+            Box::new(SyncAccessWriter::new(access.unchecked_into())?)
+        } else {
+            Box::new(StreamWriter::new(&JsFuture::from(handle.create_writable()).await?.unchecked_into::<WritableStream>()))
+        };
+    Ok(SaveTarget { handle, writer })
+}
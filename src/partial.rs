@@ -0,0 +1,270 @@
+//! Received files kept in IndexedDB as they come in, see
+//! `ClientConfig.receive_to_indexed_db`, so that what arrived before a
+//! failure can still be saved.
+//!
+//! The database `magic-wormhole-wasm` has two stores: `partials` holds a
+//! `PartialRecord` per transfer id, and `chunks` the data as `Blob`s keyed
+//! by `[id, index]`, which browsers keep on disk. Nothing is removed on its
+//! own; applications delete what they saved with `delete_partial` and
+//! clean up the rest with `collect_stale_partials`.
+//!
+//! transfer-v1 cannot resume a transfer, so the rest of a partial file can
+//! only be had by receiving it again from the start.
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::AsyncWrite;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbFactory, IdbKeyRange, IdbRequest, IdbTransaction, IdbTransactionMode};
+
+use crate::js::{self, js_error};
+use crate::metadata::FileMetadata;
+use crate::mime;
+
+const DATABASE: &str = "magic-wormhole-wasm";
+const PARTIALS: &str = "partials";
+const CHUNKS: &str = "chunks";
+
+/// How many bytes are stored per chunk.
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// What is known about a stored transfer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct PartialRecord {
+    pub(crate) id: String,
+    pub(crate) filename: String,
+    pub(crate) filesize: u64,
+    /// How many bytes are stored.
+    pub(crate) received: u64,
+    pub(crate) complete: bool,
+    /// When data was last stored, in milliseconds since the epoch.
+    pub(crate) updated: f64,
+    pub(crate) chunks: u32,
+}
+
+fn done(request: &IdbRequest) -> JsFuture {
+    JsFuture::from(js_sys::Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    }))
+}
+
+fn committed(transaction: &IdbTransaction) -> JsFuture {
+    JsFuture::from(js_sys::Promise::new(&mut |resolve, reject| {
+        transaction.set_oncomplete(Some(&resolve));
+        transaction.set_onerror(Some(&reject));
+        transaction.set_onabort(Some(&reject));
+    }))
+}
+
+async fn result(request: IdbRequest) -> Result<JsValue, JsValue> {
+    done(&request).await?;
+    request.result()
+}
+
+async fn open() -> Result<IdbDatabase, JsValue> {
+    let factory = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("indexedDB"))?;
+    if factory.is_undefined() {
+        return Err(JsValue::from_str("IndexedDB is not available"));
+    }
+    let request = factory.unchecked_into::<IdbFactory>().open_with_u32(DATABASE, 1)?;
+    let upgrade = {
+        let request = request.clone();
+        Closure::wrap(Box::new(move || {
+            if let Ok(database) = request.result() {
+                let database: IdbDatabase = database.unchecked_into();
+                let _ = database.create_object_store(PARTIALS);
+                let _ = database.create_object_store(CHUNKS);
+            }
+        }) as Box<dyn FnMut()>)
+    };
+    request.set_onupgradeneeded(Some(upgrade.as_ref().unchecked_ref()));
+    Ok(result(request.into()).await?.unchecked_into())
+}
+
+fn chunk_key(id: &str, index: u32) -> JsValue {
+    js_sys::Array::of2(&JsValue::from_str(id), &JsValue::from(index)).into()
+}
+
+/// All chunk keys of `id`.
+fn chunk_range(id: &str) -> Result<IdbKeyRange, JsValue> {
+    IdbKeyRange::bound(&chunk_key(id, 0), &chunk_key(id, u32::MAX))
+}
+
+fn transaction(database: &IdbDatabase, mode: IdbTransactionMode) -> Result<IdbTransaction, JsValue> {
+    let stores = js_sys::Array::of2(&JsValue::from_str(PARTIALS), &JsValue::from_str(CHUNKS));
+    database.transaction_with_str_sequence_and_mode(&stores, mode)
+}
+
+fn put_record(transaction: &IdbTransaction, record: &PartialRecord) -> Result<(), JsValue> {
+    let value = js::to_js(record)?;
+    transaction.object_store(PARTIALS)?.put_with_key(&value, &JsValue::from_str(&record.id))?;
+    Ok(())
+}
+
+async fn records(database: &IdbDatabase) -> Result<Vec<PartialRecord>, JsValue> {
+    let transaction = database.transaction_with_str(PARTIALS)?;
+    let values: js_sys::Array = result(transaction.object_store(PARTIALS)?.get_all()?).await?.unchecked_into();
+    Ok(values.iter().filter_map(|value| js::from_js(value).ok()).collect())
+}
+
+async fn record(database: &IdbDatabase, id: &str) -> Result<Option<PartialRecord>, JsValue> {
+    let transaction = database.transaction_with_str(PARTIALS)?;
+    let value = result(transaction.object_store(PARTIALS)?.get(&JsValue::from_str(id))?).await?;
+    Ok(js::from_js(value).ok())
+}
+
+async fn delete(database: &IdbDatabase, id: &str) -> Result<(), JsValue> {
+    let transaction = transaction(database, IdbTransactionMode::Readwrite)?;
+    transaction.object_store(PARTIALS)?.delete(&JsValue::from_str(id))?;
+    transaction.object_store(CHUNKS)?.delete(&chunk_range(id)?.into())?;
+    committed(&transaction).await.map(|_| ())
+}
+
+/// The stored data of `id` as a `File`, typed with `metadata` or else by
+/// the file name.
+async fn file(database: &IdbDatabase, record: &PartialRecord, metadata: &FileMetadata) -> Result<web_sys::File, JsValue> {
+    let transaction = database.transaction_with_str(CHUNKS)?;
+    let chunks = result(transaction.object_store(CHUNKS)?.get_all_with_key(&chunk_range(&record.id)?.into())?).await?;
+    let options = web_sys::FilePropertyBag::new();
+    options.set_type(metadata.mime_type.as_deref().unwrap_or_else(|| mime::from_filename(&record.filename)));
+    if let Some(last_modified) = metadata.last_modified {
+        options.set_last_modified(last_modified);
+    }
+    web_sys::File::new_with_blob_sequence_and_options(&chunks, &record.filename, &options)
+}
+
+/// Writes to IndexedDB in chunks of `CHUNK_SIZE`, replacing what was stored
+/// for the same id. One chunk is stored while the next one fills up.
+pub(crate) struct PartialWriter {
+    database: IdbDatabase,
+    record: PartialRecord,
+    buffer: Vec<u8>,
+    pending: Option<JsFuture>,
+}
+
+impl PartialWriter {
+    pub(crate) async fn create(id: String, filename: String, filesize: u64) -> Result<Self, JsValue> {
+        let database = open().await?;
+        delete(&database, &id).await?;
+        let record = PartialRecord {
+            id,
+            filename,
+            filesize,
+            received: 0,
+            complete: false,
+            updated: js_sys::Date::now(),
+            chunks: 0,
+        };
+        let transaction = transaction(&database, IdbTransactionMode::Readwrite)?;
+        put_record(&transaction, &record)?;
+        committed(&transaction).await?;
+        Ok(PartialWriter {
+            database,
+            record,
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+            pending: None,
+        })
+    }
+
+    pub(crate) async fn file(&self, metadata: &FileMetadata) -> Result<web_sys::File, JsValue> {
+        file(&self.database, &self.record, metadata).await
+    }
+
+    /// Starts storing the buffered data, together with the updated record.
+    fn store(&mut self, complete: bool) -> Result<(), JsValue> {
+        let transaction = transaction(&self.database, IdbTransactionMode::Readwrite)?;
+        if !self.buffer.is_empty() {
+            let data = js_sys::Uint8Array::from(&self.buffer[..]);
+            let blob = web_sys::Blob::new_with_u8_array_sequence(&js_sys::Array::of1(&data))?;
+            transaction.object_store(CHUNKS)?.put_with_key(&blob, &chunk_key(&self.record.id, self.record.chunks))?;
+            self.record.chunks += 1;
+            self.record.received += self.buffer.len() as u64;
+            self.buffer.clear();
+        }
+        self.record.complete = complete;
+        self.record.updated = js_sys::Date::now();
+        put_record(&transaction, &self.record)?;
+        self.pending = Some(committed(&transaction));
+        Ok(())
+    }
+
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let pending = match self.pending.as_mut() {
+            Some(pending) => pending,
+            None => return Poll::Ready(Ok(())),
+        };
+        let result = match Pin::new(pending).poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(result) => result,
+        };
+        self.pending = None;
+        Poll::Ready(result.map(|_| ()).map_err(js_error))
+    }
+}
+
+impl AsyncWrite for PartialWriter {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        if self.buffer.len() >= CHUNK_SIZE {
+            if self.poll_pending(cx)?.is_pending() {
+                return Poll::Pending;
+            }
+            self.store(false).map_err(js_error)?;
+        }
+        let len = buf.len().min(CHUNK_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..len]);
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_pending(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.poll_pending(cx)?.is_pending() {
+            return Poll::Pending;
+        }
+        if !self.record.complete {
+            self.store(true).map_err(js_error)?;
+        }
+        self.poll_pending(cx)
+    }
+}
+
+/// The records of all stored transfers.
+pub(crate) async fn list() -> Result<Vec<PartialRecord>, JsValue> {
+    records(&open().await?).await
+}
+
+/// What was stored for `id` so far, if anything.
+pub(crate) async fn salvage(id: &str) -> Result<Option<web_sys::File>, JsValue> {
+    let database = open().await?;
+    match record(&database, id).await? {
+        Some(record) => Ok(Some(file(&database, &record, &FileMetadata::default()).await?)),
+        None => Ok(None),
+    }
+}
+
+pub(crate) async fn remove(id: &str) -> Result<(), JsValue> {
+    delete(&open().await?, id).await
+}
+
+/// Deletes the transfers not updated for `max_age_ms` and returns how many.
+pub(crate) async fn collect_stale(max_age_ms: f64) -> Result<u32, JsValue> {
+    let database = open().await?;
+    let cutoff = js_sys::Date::now() - max_age_ms;
+    let mut count = 0;
+    for record in records(&database).await? {
+        if record.updated < cutoff {
+            delete(&database, &record.id).await?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
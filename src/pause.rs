@@ -0,0 +1,88 @@
+use std::cell::RefCell;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use futures::io::{AsyncRead, AsyncWrite};
+
+#[derive(Default)]
+struct State {
+    paused: bool,
+    wakers: Vec<Waker>,
+}
+
+/// A shared switch for holding a transfer without closing its connection.
+#[derive(Clone, Default)]
+pub(crate) struct Pause(Rc<RefCell<State>>);
+
+impl Pause {
+    pub(crate) fn set_paused(&self, paused: bool) {
+        let wakers = {
+            let mut state = self.0.borrow_mut();
+            state.paused = paused;
+            if paused {
+                return;
+            }
+            std::mem::take(&mut state.wakers)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.0.borrow().paused
+    }
+
+    fn poll_resumed(&self, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.0.borrow_mut();
+        if !state.paused {
+            return Poll::Ready(());
+        }
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// Stops reading from or writing to `inner` while `pause` is set.
+pub(crate) struct Pausable<T> {
+    inner: T,
+    pause: Pause,
+}
+
+impl<T> Pausable<T> {
+    pub(crate) fn new(inner: T, pause: &Pause) -> Self {
+        Pausable {
+            inner,
+            pause: pause.clone(),
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Pausable<T> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+        futures::ready!(self.pause.poll_resumed(cx));
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Pausable<T> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        futures::ready!(self.pause.poll_resumed(cx));
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
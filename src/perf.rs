@@ -0,0 +1,150 @@
+//! User Timing entries for transfers, see `ClientConfig.set_performance_marks`.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use magic_wormhole::{Code, Wormhole};
+use wasm_bindgen::prelude::*;
+
+use crate::events::Events;
+use crate::phase::Phase;
+use crate::stats::Progress;
+use crate::timeout::Timeout;
+use crate::trace::Direction;
+use crate::TransitInfo;
+
+#[wasm_bindgen]
+extern {
+    // Missing outside of windows and workers, so failures are ignored.
+    #[wasm_bindgen(catch, js_namespace = performance)]
+    fn mark(name: &str) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch, js_namespace = performance)]
+    fn measure(name: &str, start_mark: &str, end_mark: &str) -> Result<JsValue, JsValue>;
+}
+
+thread_local! {
+    static TRANSFERS: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Passes the events of a transfer on to `inner`, marking each phase. The
+/// marks of the `n`th transfer are named `wormhole-n:start`,
+/// `wormhole-n:<phase>`, `wormhole-n:first-byte` and `wormhole-n:last-byte`.
+/// Each phase is measured until the next one as `wormhole-n:<phase>`, the
+/// time before the first phase as `wormhole-n:connecting`, and the data
+/// from first to last byte as `wormhole-n:data`.
+pub(crate) struct Marked {
+    inner: Rc<dyn Events>,
+    prefix: String,
+    /// The mark the current span started at, and the span's name.
+    span: RefCell<(String, &'static str)>,
+    first_byte: Cell<bool>,
+}
+
+impl Marked {
+    pub(crate) fn new(inner: Rc<dyn Events>) -> Self {
+        let n = TRANSFERS.with(|transfers| {
+            transfers.set(transfers.get() + 1);
+            transfers.get()
+        });
+        let prefix = format!("wormhole-{}", n);
+        let start = format!("{}:start", prefix);
+        let _ = mark(&start);
+        Marked {
+            inner,
+            prefix,
+            span: RefCell::new((start, "connecting")),
+            first_byte: Cell::new(false),
+        }
+    }
+
+    fn mark(&self, name: &str) -> String {
+        let name = format!("{}:{}", self.prefix, name);
+        let _ = mark(&name);
+        name
+    }
+
+    fn measure(&self, name: &str, start: &str, end: &str) {
+        let _ = measure(&format!("{}:{}", self.prefix, name), start, end);
+    }
+}
+
+impl Events for Marked {
+    fn status(&self, message: &str) {
+        self.inner.status(message);
+    }
+
+    fn phase(&self, phase: Phase) {
+        let end = self.mark(phase.as_str());
+        let (start, name) = self.span.replace((end.clone(), phase.as_str()));
+        self.measure(name, &start, &end);
+        self.inner.phase(phase);
+    }
+
+    fn welcome(&self, message: &str) {
+        self.inner.welcome(message);
+    }
+
+    fn code(&self, code: &Code) {
+        self.inner.code(code);
+    }
+
+    fn alternate_code(&self, code: &Code) {
+        self.inner.alternate_code(code);
+    }
+
+    fn code_countdown(&self, remaining_ms: f64) {
+        self.inner.code_countdown(remaining_ms);
+    }
+
+    fn code_expired(&self) {
+        self.inner.code_expired();
+    }
+
+    fn connected(&self, wormhole: &Wormhole) {
+        self.inner.connected(wormhole);
+    }
+
+    fn transit(&self, info: TransitInfo) {
+        self.inner.transit(info);
+    }
+
+    fn stalled(&self, idle_ms: f64) {
+        self.inner.stalled(idle_ms);
+    }
+
+    fn network(&self, online: bool) {
+        self.inner.network(online);
+    }
+
+    fn wrong_code(&self) {
+        self.inner.wrong_code();
+    }
+
+    fn timed_out(&self, timeout: Timeout) {
+        self.inner.timed_out(timeout);
+    }
+
+    fn rejected(&self, message: &str) {
+        self.inner.rejected(message);
+    }
+
+    fn progress(&self, progress: Progress) {
+        if progress.current > 0 && !self.first_byte.replace(true) {
+            self.mark("first-byte");
+        }
+        if progress.current == progress.total {
+            let end = self.mark("last-byte");
+            self.measure("data", &format!("{}:first-byte", self.prefix), &end);
+        }
+        self.inner.progress(progress);
+    }
+
+    fn error(&self, message: &str) {
+        self.inner.error(message);
+    }
+
+    fn trace(&self, direction: Direction, event: &str, fields: serde_json::Value) {
+        self.inner.trace(direction, event, fields);
+    }
+}
@@ -0,0 +1,33 @@
+/// The coarse steps of a transfer, in the order they happen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// The rendezvous server allocated a code (sending only).
+    CodeAllocated,
+    /// The peer joined and both sides derived the session key.
+    PeerConnected,
+    /// The session was confirmed, by the verifier callback if there is one.
+    KeyConfirmed,
+    /// Looking for a direct or relayed transit connection.
+    TransitConnecting,
+    TransitConnected,
+    Transferring,
+    /// Checking the received data (receiving only).
+    Verifying,
+    Done,
+}
+
+impl Phase {
+    /// The name used in events, like `key-confirmed`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Phase::CodeAllocated => "code-allocated",
+            Phase::PeerConnected => "peer-connected",
+            Phase::KeyConfirmed => "key-confirmed",
+            Phase::TransitConnecting => "transit-connecting",
+            Phase::TransitConnected => "transit-connected",
+            Phase::Transferring => "transferring",
+            Phase::Verifying => "verifying",
+            Phase::Done => "done",
+        }
+    }
+}
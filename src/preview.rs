@@ -0,0 +1,113 @@
+//! Handing the start of a received file to JS while the rest is still
+//! coming in, see `ClientConfig.set_preview_callback`.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::AsyncWrite;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::cancel::Cancel;
+use crate::{compression, size};
+
+/// How many bytes are previewed, by default.
+pub(crate) const DEFAULT_PREVIEW_SIZE: usize = 64 * 1024;
+
+/// The callback and what it is called with.
+pub(crate) struct Preview {
+    pub(crate) callback: js_sys::Function,
+    pub(crate) size: usize,
+    pub(crate) filename: String,
+    pub(crate) filesize: u64,
+    /// Whether the data is gzip compressed, in which case the preview is
+    /// what its start decompresses to.
+    pub(crate) compressed: bool,
+    pub(crate) cancel: Cancel,
+}
+
+impl Preview {
+    fn show(self, data: &[u8]) {
+        let data = if self.compressed {
+            compression::decompress_prefix(data, self.size)
+        } else {
+            data.to_vec()
+        };
+        let result = self.callback.call3(
+            &JsValue::NULL,
+            &js_sys::Uint8Array::from(&data[..]),
+            &JsValue::from_str(&self.filename),
+            &size::declared(self.filesize),
+        );
+        let cancel = self.cancel;
+        match result {
+            Ok(result) => match result.dyn_into::<js_sys::Promise>() {
+                Ok(promise) => wasm_bindgen_futures::spawn_local(async move {
+                    match wasm_bindgen_futures::JsFuture::from(promise).await {
+                        Ok(result) if result == JsValue::FALSE => cancel.cancel(),
+                        Ok(_) => (),
+                        Err(e) => console_log!("Error in preview callback: {:?}", e),
+                    }
+                }),
+                Err(result) if result == JsValue::FALSE => cancel.cancel(),
+                Err(_) => (),
+            },
+            Err(e) => console_log!("Error in preview callback: {:?}", e),
+        }
+    }
+}
+
+/// Collects the first bytes written to `inner` and shows them once there
+/// are enough, or when the file turns out to be shorter.
+pub(crate) struct PreviewWriter<W> {
+    inner: W,
+    preview: Option<Preview>,
+    buffer: Vec<u8>,
+}
+
+impl<W> PreviewWriter<W> {
+    pub(crate) fn new(inner: W, preview: Option<Preview>) -> Self {
+        PreviewWriter {
+            inner,
+            preview,
+            buffer: Vec::new(),
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn show(&mut self) {
+        if let Some(preview) = self.preview.take() {
+            preview.show(&std::mem::take(&mut self.buffer));
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for PreviewWriter<W> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let written = match Pin::new(&mut self.inner).poll_write(cx, buf) {
+            Poll::Ready(Ok(written)) => written,
+            other => return other,
+        };
+        if let Some(size) = self.preview.as_ref().map(|preview| preview.size) {
+            let missing = size - self.buffer.len();
+            self.buffer.extend_from_slice(&buf[..written.min(missing)]);
+            if self.buffer.len() == size {
+                self.show();
+            }
+        }
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.show();
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
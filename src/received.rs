@@ -0,0 +1,139 @@
+use wasm_bindgen::prelude::*;
+
+use crate::metadata::FileMetadata;
+use crate::mime;
+use crate::offer::Selection;
+use crate::TransferStats;
+
+/// The size of the buffers returned by `ReceiveResult.into_buffers`, by default.
+const DEFAULT_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// A received file, along with what is known about its transfer.
+#[wasm_bindgen]
+pub struct ReceiveResult {
+    pub(crate) data: Vec<u8>,
+    pub(crate) filename: String,
+    pub(crate) raw_filename: String,
+    pub(crate) filesize: u64,
+    pub(crate) metadata: FileMetadata,
+    /// The archive entries picked when the offer was accepted.
+    pub(crate) selection: Option<Selection>,
+    pub(crate) sha256: String,
+    pub(crate) stats: TransferStats,
+}
+
+impl ReceiveResult {
+    pub fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+#[wasm_bindgen]
+impl ReceiveResult {
+    /// The name offered by the peer, made safe by `sanitize_filename`.
+    #[wasm_bindgen(getter)]
+    pub fn filename(&self) -> String {
+        self.filename.clone()
+    }
+
+    /// The name exactly as offered by the peer.
+    #[wasm_bindgen(getter)]
+    pub fn raw_filename(&self) -> String {
+        self.raw_filename.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn filesize(&self) -> u64 {
+        self.filesize
+    }
+
+    /// SHA-256 of the data, as lowercase hex.
+    #[wasm_bindgen(getter)]
+    pub fn sha256(&self) -> String {
+        self.sha256.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn stats(&self) -> TransferStats {
+        self.stats.clone()
+    }
+
+    /// A copy of the data.
+    pub fn data(&self) -> js_sys::Uint8Array {
+        js_sys::Uint8Array::from(&self.data[..])
+    }
+
+    /// The MIME type the sender announced, otherwise the one for the
+    /// extension of `filename`, or `application/octet-stream`.
+    #[wasm_bindgen(getter)]
+    pub fn mime_type(&self) -> String {
+        self.metadata.mime_type.clone().unwrap_or_else(|| mime::from_filename(&self.filename).to_owned())
+    }
+
+    /// When the file was last modified on the sender's side, in
+    /// milliseconds since the epoch, if the sender announced it.
+    #[wasm_bindgen(getter)]
+    pub fn last_modified(&self) -> Option<f64> {
+        self.metadata.last_modified
+    }
+
+    /// The note the sender attached, see `ClientConfig.set_description`.
+    #[wasm_bindgen(getter)]
+    pub fn description(&self) -> Option<String> {
+        self.metadata.description.clone()
+    }
+
+    /// The data as a `Blob` of type `mime_type`.
+    pub fn blob(&self) -> Result<web_sys::Blob, JsValue> {
+        self.to_blob(None)
+    }
+
+    /// The data as a `Blob` of type `mime`, by default `mime_type`.
+    pub fn to_blob(&self, mime: Option<String>) -> Result<web_sys::Blob, JsValue> {
+        let options = web_sys::BlobPropertyBag::new();
+        options.set_type(&mime.unwrap_or_else(|| self.mime_type()));
+        // The view is only valid until the wasm memory grows, but the Blob
+        // copies it right away
+        let view = unsafe { js_sys::Uint8Array::view(&self.data) };
+        web_sys::Blob::new_with_u8_array_sequence_and_options(&js_sys::Array::of1(&view), &options)
+    }
+
+    /// An object URL for `to_blob(mime)`, for a download link or an image
+    /// preview. Release it with `URL.revokeObjectURL` once it is not needed.
+    pub fn to_object_url(&self, mime: Option<String>) -> Result<String, JsValue> {
+        web_sys::Url::create_object_url_with_blob(&self.to_blob(mime)?)
+    }
+
+    /// Copies the data to the start of `target`, which has to be large
+    /// enough, e.g. a file mapped by the application.
+    pub fn copy_into(&self, target: &js_sys::Uint8Array) -> Result<(), JsValue> {
+        if (target.length() as usize) < self.data.len() {
+            return Err(JsValue::from_str(&format!("The target holds {} bytes, {} are needed", target.length(), self.data.len())));
+        }
+        target.subarray(0, self.data.len() as u32).copy_from(&self.data);
+        Ok(())
+    }
+
+    /// Moves the data into `ArrayBuffer`s of at most `buffer_size` bytes (1
+    /// MiB by default), in order, to pass on in a `postMessage` transfer
+    /// list or to `new Blob(buffers)`. The result can't be used afterwards.
+    ///
+    /// Each buffer's memory is freed once it has been copied, so the data
+    /// is never held twice. The wasm memory does not shrink, but the next
+    /// transfer reuses it.
+    pub fn into_buffers(mut self, buffer_size: Option<usize>) -> js_sys::Array {
+        let buffer_size = buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE).max(1);
+        let mut buffers = Vec::new();
+        while !self.data.is_empty() {
+            let start = (self.data.len() - 1) / buffer_size * buffer_size;
+            buffers.push(js_sys::Uint8Array::from(&self.data[start..]).buffer());
+            self.data.truncate(start);
+            self.data.shrink_to_fit();
+        }
+        buffers.into_iter().rev().collect()
+    }
+}
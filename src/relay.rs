@@ -0,0 +1,84 @@
+use js_sys::{Array, Promise};
+use url::Url;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::timer;
+
+/// How a relay fared when probed, see `by_latency`.
+enum Probe {
+    /// Accepted a WebSocket connection after this many milliseconds.
+    Open(f64),
+    /// Is not a WebSocket relay, so only the peer may be able to use it.
+    Unprobed,
+    Failed,
+}
+
+/// Opens a WebSocket to `url` and times how long it takes to open, giving
+/// up after `timeout_ms`.
+async fn probe(url: &Url, timeout_ms: i32) -> Probe {
+    if !matches!(url.scheme(), "ws" | "wss") {
+        return Probe::Unprobed;
+    }
+    let start = js_sys::Date::now();
+    let socket = match web_sys::WebSocket::new(url.as_str()) {
+        Ok(socket) => socket,
+        Err(_) => return Probe::Failed,
+    };
+    let opened = Promise::new(&mut |resolve, reject| {
+        socket.set_onopen(Some(&resolve));
+        socket.set_onerror(Some(&reject));
+    });
+
+    let result = JsFuture::from(Promise::race(&Array::of2(&opened, &timer::deadline(timeout_ms)))).await;
+
+    socket.set_onopen(None);
+    socket.set_onerror(None);
+    let _ = socket.close();
+
+    match result {
+        Ok(_) => Probe::Open(js_sys::Date::now() - start),
+        Err(_) => Probe::Failed,
+    }
+}
+
+/// The URL `callback` returns for the relay at `url`, see
+/// `ClientConfig.set_relay_auth_callback`.
+pub(crate) async fn authenticate(callback: &js_sys::Function, url: &str) -> Result<String, JsValue> {
+    let mut result = callback.call1(&JsValue::NULL, &JsValue::from_str(url))?;
+    if let Some(promise) = result.dyn_ref::<Promise>() {
+        result = JsFuture::from(promise.clone()).await?;
+    }
+    result
+        .as_string()
+        .ok_or_else(|| JsValue::from_str(&format!("The relay auth callback returned no URL for {}", url)))
+}
+
+/// Orders the relays for the transit connection. The WebSocket relays are
+/// probed concurrently and those that accepted a connection come first,
+/// fastest first. Relays that cannot be probed from here, like `tcp://`
+/// ones, follow, and those that failed their probe come last instead of
+/// being dropped, as the failure may have been a passing one. Otherwise the
+/// configured order is kept.
+pub(crate) async fn by_latency(urls: &[Url], timeout_ms: i32) -> Result<Vec<Url>, JsValue> {
+    match urls {
+        [] => Err(JsValue::from_str("No transit relay configured")),
+        [url] => Ok(vec![url.clone()]),
+        _ => {
+            let probes = futures::future::join_all(urls.iter().map(|url| probe(url, timeout_ms))).await;
+            let mut ranked: Vec<_> = urls.iter().cloned().zip(probes).collect();
+            // Stable, so that ties keep the configured order.
+            ranked.sort_by(|(_, a), (_, b)| rank(a).partial_cmp(&rank(b)).unwrap_or(std::cmp::Ordering::Equal));
+            Ok(ranked.into_iter().map(|(url, _)| url).collect())
+        }
+    }
+}
+
+fn rank(probe: &Probe) -> (u8, f64) {
+    match probe {
+        Probe::Open(ms) => (0, *ms),
+        Probe::Unprobed => (1, 0.0),
+        Probe::Failed => (2, 0.0),
+    }
+}
@@ -0,0 +1,154 @@
+//! Forwards panics and transfer errors to the callback registered with
+//! `set_error_reporter`, for crash reporting services.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Once;
+
+use magic_wormhole::{Code, Wormhole};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::events::Events;
+use crate::js;
+use crate::phase::Phase;
+use crate::stats::Progress;
+use crate::timeout::Timeout;
+use crate::trace::Direction;
+use crate::{ErrorReporter, TransitInfo};
+
+#[wasm_bindgen]
+extern {
+    type Error;
+
+    #[wasm_bindgen(constructor)]
+    fn new() -> Error;
+
+    #[wasm_bindgen(structural, method, getter)]
+    fn stack(error: &Error) -> String;
+}
+
+thread_local! {
+    static REPORTER: RefCell<Option<js_sys::Function>> = RefCell::new(None);
+    /// The phase the most recent transfer reached.
+    static PHASE: Cell<Option<Phase>> = const { Cell::new(None) };
+}
+
+static PANIC_HOOK: Once = Once::new();
+
+pub(crate) fn set_reporter(reporter: Option<ErrorReporter>) {
+    REPORTER.with(|current| *current.borrow_mut() = reporter.map(JsCast::unchecked_into));
+    PANIC_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            previous(info);
+            report("panic", &info.to_string());
+        }));
+    });
+}
+
+pub(crate) fn is_set() -> bool {
+    REPORTER.with(|reporter| reporter.borrow().is_some())
+}
+
+fn report(kind: &str, message: &str) {
+    let reporter = match REPORTER.with(|reporter| reporter.borrow().clone()) {
+        Some(reporter) => reporter,
+        None => return,
+    };
+    let record = serde_json::json!({
+        "kind": kind,
+        "message": message,
+        "backtrace": Error::new().stack(),
+        "phase": PHASE.with(Cell::get).map(Phase::as_str),
+    });
+    // The reporter must not fail a transfer, and a panic may have left
+    // nothing better to log to.
+    let _ = reporter.call1(&JsValue::NULL, &js::to_js_or_undefined(&record));
+}
+
+/// Passes the events of a transfer on to `inner`, reporting its errors.
+pub(crate) struct Reported {
+    inner: Rc<dyn Events>,
+}
+
+impl Reported {
+    pub(crate) fn new(inner: Rc<dyn Events>) -> Self {
+        PHASE.with(|phase| phase.set(None));
+        Reported { inner }
+    }
+}
+
+impl Events for Reported {
+    fn status(&self, message: &str) {
+        self.inner.status(message);
+    }
+
+    fn phase(&self, phase: Phase) {
+        PHASE.with(|current| current.set(Some(phase)));
+        self.inner.phase(phase);
+    }
+
+    fn welcome(&self, message: &str) {
+        self.inner.welcome(message);
+    }
+
+    fn code(&self, code: &Code) {
+        self.inner.code(code);
+    }
+
+    fn alternate_code(&self, code: &Code) {
+        self.inner.alternate_code(code);
+    }
+
+    fn code_countdown(&self, remaining_ms: f64) {
+        self.inner.code_countdown(remaining_ms);
+    }
+
+    fn code_expired(&self) {
+        self.inner.code_expired();
+    }
+
+    fn connected(&self, wormhole: &Wormhole) {
+        self.inner.connected(wormhole);
+    }
+
+    fn transit(&self, info: TransitInfo) {
+        self.inner.transit(info);
+    }
+
+    fn stalled(&self, idle_ms: f64) {
+        self.inner.stalled(idle_ms);
+    }
+
+    fn network(&self, online: bool) {
+        self.inner.network(online);
+    }
+
+    // Wrong codes, timeouts and rejections are up to the users, they are
+    // not reported.
+    fn wrong_code(&self) {
+        self.inner.wrong_code();
+    }
+
+    fn timed_out(&self, timeout: Timeout) {
+        self.inner.timed_out(timeout);
+    }
+
+    fn rejected(&self, message: &str) {
+        self.inner.rejected(message);
+    }
+
+    fn progress(&self, progress: Progress) {
+        self.inner.progress(progress);
+    }
+
+    fn error(&self, message: &str) {
+        report("error", message);
+        self.inner.error(message);
+    }
+
+    fn trace(&self, direction: Direction, event: &str, fields: serde_json::Value) {
+        self.inner.trace(direction, event, fields);
+    }
+}
@@ -0,0 +1,51 @@
+//! Which JS runtime the module was loaded in, for the few features that
+//! differ between them. Everything else only uses globals that browsers,
+//! Node.js, Deno and Bun all have: `WebSocket`, `setTimeout`, `console`,
+//! `crypto` and `performance`.
+
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Runtime {
+    /// A browser page, with a `window`.
+    Window,
+    /// A web worker or service worker.
+    Worker,
+    Node,
+    Deno,
+    Bun,
+}
+
+impl Runtime {
+    pub(crate) fn detect() -> Self {
+        let global = js_sys::global();
+        let has = |name: &str| js_sys::Reflect::get(&global, &JsValue::from_str(name)).is_ok_and(|value| !value.is_undefined());
+        // Deno and Bun emulate `process`, and Deno 1 also defines `window`,
+        // so they are checked first.
+        if has("Deno") {
+            Runtime::Deno
+        } else if has("Bun") {
+            Runtime::Bun
+        } else if js_sys::Reflect::get(&global, &JsValue::from_str("process"))
+            .and_then(|process| js_sys::Reflect::get(&process, &JsValue::from_str("versions")))
+            .and_then(|versions| js_sys::Reflect::get(&versions, &JsValue::from_str("node")))
+            .is_ok_and(|node| node.is_string())
+        {
+            Runtime::Node
+        } else if web_sys::window().is_some() {
+            Runtime::Window
+        } else {
+            Runtime::Worker
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Runtime::Window => "window",
+            Runtime::Worker => "worker",
+            Runtime::Node => "node",
+            Runtime::Deno => "deno",
+            Runtime::Bun => "bun",
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::AsyncWrite;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::directory::FileSystemFileHandle;
+use crate::metadata::FileMetadata;
+use crate::mime;
+use crate::TransferStats;
+
+/// A file handle being written as data is received.
+pub(crate) struct SaveTarget {
+    pub(crate) handle: FileSystemFileHandle,
+    pub(crate) writer: Box<dyn AsyncWrite + Unpin>,
+}
+
+impl AsyncWrite for SaveTarget {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.writer).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.writer).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.writer).poll_close(cx)
+    }
+}
+
+/// A file received straight to disk, see `ClientConfig.receive_to_opfs` and
+/// `ClientConfig.receive_to_handle`.
+#[wasm_bindgen]
+pub struct SavedFile {
+    pub(crate) handle: FileSystemFileHandle,
+    pub(crate) raw_filename: String,
+    pub(crate) filesize: u64,
+    pub(crate) metadata: FileMetadata,
+    pub(crate) sha256: String,
+    pub(crate) stats: TransferStats,
+}
+
+#[wasm_bindgen]
+impl SavedFile {
+    /// The handle of the written file.
+    #[wasm_bindgen(getter)]
+    pub fn handle(&self) -> FileSystemFileHandle {
+        self.handle.clone()
+    }
+
+    /// The name the file was saved as.
+    #[wasm_bindgen(getter)]
+    pub fn filename(&self) -> String {
+        self.handle.name()
+    }
+
+    /// The name exactly as offered by the peer.
+    #[wasm_bindgen(getter)]
+    pub fn raw_filename(&self) -> String {
+        self.raw_filename.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn filesize(&self) -> u64 {
+        self.filesize
+    }
+
+    /// Like `ReceiveResult.mime_type`.
+    #[wasm_bindgen(getter)]
+    pub fn mime_type(&self) -> String {
+        self.metadata.mime_type.clone().unwrap_or_else(|| mime::from_filename(&self.filename()).to_owned())
+    }
+
+    /// Like `ReceiveResult.last_modified`.
+    #[wasm_bindgen(getter)]
+    pub fn last_modified(&self) -> Option<f64> {
+        self.metadata.last_modified
+    }
+
+    /// Like `ReceiveResult.description`.
+    #[wasm_bindgen(getter)]
+    pub fn description(&self) -> Option<String> {
+        self.metadata.description.clone()
+    }
+
+    /// SHA-256 of the data, as lowercase hex.
+    #[wasm_bindgen(getter)]
+    pub fn sha256(&self) -> String {
+        self.sha256.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn stats(&self) -> TransferStats {
+        self.stats.clone()
+    }
+
+    /// The saved file, read from disk on demand.
+    pub async fn file(&self) -> Result<web_sys::File, JsValue> {
+        Ok(JsFuture::from(self.handle.get_file()).await?.unchecked_into())
+    }
+}
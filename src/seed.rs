@@ -0,0 +1,63 @@
+//! Codes for peers that paired before, derived from a secret they both
+//! kept, so that they can connect again without exchanging a code.
+
+use sha2::{Digest, Sha256};
+use wasm_bindgen::prelude::*;
+
+use crate::wordlist;
+
+/// How many words seeded codes have. Nobody has to type them, so they are
+/// longer than usual.
+const WORDS: usize = 4;
+
+/// A secret shared by two peers after a transfer, from
+/// `SessionKey.pairingSeed()`. Store its `bytes` on both sides; each later
+/// `code(counter)` is the same on both, for `ClientConfig.set_send_code` on
+/// one side and the usual receive on the other. Both sides have to pick the
+/// same counter, e.g. by counting their sessions or from the current hour.
+#[wasm_bindgen]
+pub struct PairingSeed {
+    seed: [u8; 32],
+}
+
+impl PairingSeed {
+    pub(crate) fn new(seed: [u8; 32]) -> Self {
+        PairingSeed { seed }
+    }
+}
+
+#[wasm_bindgen]
+impl PairingSeed {
+    /// Restores a seed from its `bytes`.
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<PairingSeed, JsValue> {
+        let mut seed = [0; 32];
+        if bytes.len() != seed.len() {
+            return Err(JsValue::from_str("A pairing seed has 32 bytes"));
+        }
+        seed.copy_from_slice(bytes);
+        Ok(PairingSeed { seed })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn bytes(&self) -> js_sys::Uint8Array {
+        js_sys::Uint8Array::from(&self.seed[..])
+    }
+
+    /// The code for the `counter`th session, with a six digit nameplate so
+    /// that it does not collide with the ones servers allocate.
+    pub fn code(&self, counter: u32) -> String {
+        let digest = Sha256::new()
+            .chain_update(b"magic-wormhole-wasm/pairing-code")
+            .chain_update(self.seed)
+            .chain_update(counter.to_be_bytes())
+            .finalize();
+        let nameplate = 100_000 + u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) % 900_000;
+        let words: Vec<&str> = digest[4..4 + WORDS]
+            .iter()
+            .enumerate()
+            .map(|(position, &byte)| wordlist::pgp_word(byte, position))
+            .collect();
+        format!("{}-{}", nameplate, words.join("-"))
+    }
+}
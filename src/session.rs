@@ -0,0 +1,390 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use magic_wormhole::{Code, Wormhole};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::cancel::Cancel;
+use crate::context::TransferContext;
+use crate::js;
+use crate::manager::Registry;
+use crate::pause::Pause;
+use crate::phase::Phase;
+use crate::stats::Progress;
+use crate::stream::ReadableStreamDefaultController;
+use crate::timeout::Timeout;
+use crate::events::Events;
+use crate::extension::KeepAlive;
+use crate::{ClientConfig, NodeFileHandle, ReadableStream, SessionKey, TransitInfo, WormholeCode};
+
+/// What a session reached so far, see `WormholeSession.state`.
+#[derive(Default, serde::Serialize)]
+struct SessionState {
+    phase: Option<&'static str>,
+    code: Option<String>,
+    bytes: u64,
+    total: Option<u64>,
+    error: Option<String>,
+}
+
+/// The callbacks registered with `WormholeSession.on`, by event name.
+#[derive(Default)]
+struct Listeners {
+    callbacks: RefCell<HashMap<String, Vec<js_sys::Function>>>,
+    /// The `events()` streams still open.
+    streams: RefCell<Vec<ReadableStreamDefaultController>>,
+    state: RefCell<SessionState>,
+}
+
+/// The events after which a session does nothing more.
+const FINAL_EVENTS: &[&str] = &["done", "error", "rejected", "timeout", "wrong-code", "expired"];
+
+impl Listeners {
+    fn emit(&self, event: &str, args: &[JsValue]) {
+        let args: js_sys::Array = args.iter().collect();
+        self.enqueue(event, &args);
+        let callbacks = match self.callbacks.borrow().get(event) {
+            Some(callbacks) => callbacks.clone(),
+            None => return,
+        };
+        for callback in callbacks {
+            if let Err(e) = callback.apply(&JsValue::NULL, &args) {
+                console_log!("Error in {} listener: {:?}", event, e);
+            }
+        }
+    }
+
+    /// Passes the event to the `events()` streams as `{type, args}`, and
+    /// closes them after the final one. Streams whose reader cancelled them
+    /// are dropped.
+    fn enqueue(&self, event: &str, args: &js_sys::Array) {
+        let mut streams = self.streams.borrow_mut();
+        if streams.is_empty() {
+            return;
+        }
+        let object = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&object, &JsValue::from_str("type"), &JsValue::from_str(event));
+        let _ = js_sys::Reflect::set(&object, &JsValue::from_str("args"), args);
+        streams.retain(|controller| controller.enqueue(&object).is_ok());
+        if FINAL_EVENTS.contains(&event) {
+            for controller in streams.drain(..) {
+                let _ = controller.close();
+            }
+        }
+    }
+
+    fn first(&self, event: &str) -> Option<js_sys::Function> {
+        self.callbacks.borrow().get(event)?.first().cloned()
+    }
+}
+
+impl Events for Listeners {
+    fn status(&self, message: &str) {
+        self.emit("status", &[JsValue::from_str(message)]);
+    }
+
+    fn phase(&self, phase: Phase) {
+        self.state.borrow_mut().phase = Some(phase.as_str());
+        self.emit("phase", &[JsValue::from_str(phase.as_str())]);
+    }
+
+    fn welcome(&self, message: &str) {
+        self.emit("welcome", &[JsValue::from_str(message)]);
+    }
+
+    fn code(&self, code: &Code) {
+        self.state.borrow_mut().code = Some(code.to_string());
+        self.emit("code", &[WormholeCode::from(code).into()]);
+    }
+
+    fn alternate_code(&self, code: &Code) {
+        self.emit("alternate-code", &[WormholeCode::from(code).into()]);
+    }
+
+    fn code_countdown(&self, remaining_ms: f64) {
+        self.emit("countdown", &[JsValue::from(remaining_ms)]);
+    }
+
+    fn code_expired(&self) {
+        self.emit("expired", &[]);
+    }
+
+    fn connected(&self, wormhole: &Wormhole) {
+        self.emit("connected", &[
+            js::to_js_or_undefined(&wormhole.peer_version()),
+            SessionKey::new(wormhole).into(),
+        ]);
+    }
+
+    fn transit(&self, info: TransitInfo) {
+        self.emit("transit", &[info.into()]);
+    }
+
+    fn stalled(&self, idle_ms: f64) {
+        self.emit("stalled", &[JsValue::from(idle_ms)]);
+    }
+
+    fn network(&self, online: bool) {
+        self.emit(if online { "resumed" } else { "offline" }, &[]);
+    }
+
+    fn wrong_code(&self) {
+        self.state.borrow_mut().error = Some("Wrong code".to_owned());
+        self.emit("wrong-code", &[]);
+    }
+
+    fn timed_out(&self, timeout: Timeout) {
+        self.state.borrow_mut().error = Some(timeout.message().to_owned());
+        self.emit("timeout", &[JsValue::from_str(timeout.as_str()), JsValue::from_str(timeout.message())]);
+    }
+
+    fn rejected(&self, message: &str) {
+        self.state.borrow_mut().error = Some(message.to_owned());
+        self.emit("rejected", &[JsValue::from_str(message)]);
+    }
+
+    fn progress(&self, progress: Progress) {
+        {
+            let mut state = self.state.borrow_mut();
+            state.bytes = progress.current;
+            state.total = Some(progress.total);
+        }
+        self.emit("progress", &[
+            JsValue::from(progress.current as f64),
+            JsValue::from(progress.total as f64),
+            JsValue::from(progress.bytes_per_second),
+            JsValue::from(progress.smoothed_bytes_per_second),
+            JsValue::from(progress.eta_ms),
+            progress.entry.as_ref().map_or(JsValue::UNDEFINED, js::to_js_or_undefined),
+        ]);
+    }
+
+    fn error(&self, message: &str) {
+        self.state.borrow_mut().error = Some(message.to_owned());
+        self.emit("error", &[JsValue::from_str(message)]);
+    }
+}
+
+/// A single send or receive, reporting its lifecycle as events:
+///
+/// - `status(message)`: what the session is doing, in words
+/// - `phase(name)`: the session reached the next step, one of
+///   `code-allocated` (sending only), `peer-connected`, `key-confirmed`,
+///   `transit-connecting`, `transit-connected`, `transferring`, `verifying`
+///   (receiving only) and `done`
+/// - `welcome(message)`: a notice from the rendezvous server operator
+/// - `code(code)`: the `WormholeCode` to hand to the peer (sending only)
+/// - `alternate-code(code)`: a second code for the same file, see
+///   `ClientConfig.set_alternate_code_length` (sending only)
+/// - `countdown(remainingMs)`: about once a second until the code expires,
+///   see `ClientConfig.set_code_expiry` (sending only)
+/// - `expired()`: nobody used the code in time
+/// - `verifier(verifier)`: the session `Verifier`; the first listener can
+///   gate the transfer like `ClientConfig.send`'s `on_verifier`
+/// - `connected(appVersions, key)`: the peer has connected and sent these
+///   app versions, see `ClientConfig.set_app_versions`; `key` is the
+///   `SessionKey` to derive application keys from
+/// - `transit(info)`: the `TransitInfo` of the connection carrying the data
+/// - `progress(current, total, bytesPerSecond, smoothedBytesPerSecond, etaMs, entry)`:
+///   bytes transferred so far, the rate over the last second, a smoothed
+///   rate that suits display, and the time left at that rate (`undefined`
+///   until there is a rate); for an archive from `send_files` or
+///   `send_directory`, `entry` is the `{name, index, count, current,
+///   total}` of the file in it being transferred
+/// - `stalled(idleMs)`: no data moved for a while, see
+///   `ClientConfig.set_stall_warning`
+/// - `offline()` and `resumed()`: the browser lost its network connection,
+///   which holds the transfer, and got it back
+/// - `done(result)`: the transfer finished; `result` is the `TransferStats`
+///   when sending and the `ReceiveResult` when receiving, or the
+///   `TransferStats` again when receiving into a Node.js file
+/// - `wrong-code()`: the peer used a different code; the user can check it
+///   and try again
+/// - `timeout(stage, message)`: a timeout set with `ClientConfig.set_timeouts`
+///   expired; `stage` is `peer`, `key-exchange`, `transit` or `stalled`
+/// - `rejected(message)`: the peer rejected the offer or aborted the transfer
+/// - `error(message)`: the transfer failed for any other reason
+///
+/// The session is closed with `close()`, or when the page is unloaded.
+/// Sessions need no DOM, so they also run under Node.js once a `WebSocket`
+/// global is installed (Node 22 has one built in), and in the service worker
+/// of a Manifest V3 extension, which they keep alive while they run.
+#[wasm_bindgen]
+pub struct WormholeSession {
+    config: ClientConfig,
+    listeners: Rc<Listeners>,
+    cancel: Cancel,
+    pause: Pause,
+    on_pagehide: Option<Closure<dyn FnMut()>>,
+    registration: Option<(u32, Rc<Registry>)>,
+}
+
+#[wasm_bindgen]
+impl WormholeSession {
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: &ClientConfig) -> WormholeSession {
+        let cancel = Cancel::default();
+        let on_pagehide = web_sys::window().map(|window| {
+            let cancel = cancel.clone();
+            let closure = Closure::wrap(Box::new(move || cancel.cancel()) as Box<dyn FnMut()>);
+            let _ = window.add_event_listener_with_callback("pagehide", closure.as_ref().unchecked_ref());
+            closure
+        });
+
+        WormholeSession {
+            config: config.clone(),
+            listeners: Rc::new(Listeners::default()),
+            cancel,
+            pause: Pause::default(),
+            on_pagehide,
+            registration: None,
+        }
+    }
+
+    /// The id of the session in the `TransferManager` that created it.
+    #[wasm_bindgen(getter)]
+    pub fn id(&self) -> Option<u32> {
+        self.registration.as_ref().map(|(id, _)| *id)
+    }
+
+    /// Registers `callback` for `event`, see the list above.
+    pub fn on(&self, event: String, callback: js_sys::Function) {
+        self.listeners.callbacks.borrow_mut().entry(event).or_default().push(callback);
+    }
+
+    /// The events from now on as a `ReadableStream` of `{type, args}`
+    /// objects, where `type` is the event name and `args` the arguments a
+    /// listener would get. The stream ends after `done` or any event that
+    /// ends the session, so it can be consumed with `for await (const event
+    /// of session.events())`. The `verifier` event cannot gate the transfer
+    /// this way, use `on("verifier", ...)` for that.
+    pub fn events(&self) -> ReadableStream {
+        let controller = Rc::new(RefCell::new(None));
+        let start = {
+            let controller = controller.clone();
+            Closure::once_into_js(move |started: ReadableStreamDefaultController| {
+                *controller.borrow_mut() = Some(started);
+            })
+        };
+        let source = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&source, &JsValue::from_str("start"), &start);
+        let stream = ReadableStream::new(&source);
+        // `start` is called by the constructor
+        if let Some(controller) = controller.borrow_mut().take() {
+            self.listeners.streams.borrow_mut().push(controller);
+        }
+        stream
+    }
+
+    #[wasm_bindgen(js_name = sendFile)]
+    pub async fn send_file(&self, file: web_sys::File) {
+        let _keepalive = KeepAlive::start();
+        if let Some(stats) = self.config.send_file(file, &self.context(), None).await {
+            self.listeners.emit("done", &[stats.into()]);
+        }
+    }
+
+    /// Receives the file sent with `code`. If `expected_sha256` is given, the
+    /// session fails unless the received data has that SHA-256 (as hex).
+    pub async fn receive(&self, code: String, expected_sha256: Option<String>) {
+        let _keepalive = KeepAlive::start();
+        let context = TransferContext {
+            expected_sha256,
+            ..self.context()
+        };
+        if let Some(result) = self.config.receive_file(code, &context).await {
+            self.listeners.emit("done", &[result.into()]);
+        }
+    }
+
+    /// Sends the file open as `handle`, a `FileHandle` from Node.js'
+    /// `fs.promises.open()`, under the name `name`.
+    #[wasm_bindgen(js_name = sendFileHandle)]
+    pub async fn send_file_handle(&self, handle: NodeFileHandle, name: String) {
+        let _keepalive = KeepAlive::start();
+        if let Some(stats) = self.config.send_file_handle(handle, name, &self.context()).await {
+            self.listeners.emit("done", &[stats.into()]);
+        }
+    }
+
+    /// Receives the file sent with `code` into `handle`, a `FileHandle`
+    /// opened for writing with `fs.promises.open(path, "w")`, which is left
+    /// open. Like `receive` otherwise.
+    #[wasm_bindgen(js_name = receiveToFileHandle)]
+    pub async fn receive_to_file_handle(&self, code: String, handle: NodeFileHandle, expected_sha256: Option<String>) {
+        let _keepalive = KeepAlive::start();
+        let context = TransferContext {
+            expected_sha256,
+            ..self.context()
+        };
+        if let Some(stats) = self.config.receive_to_file_handle(code, handle, &context).await {
+            self.listeners.emit("done", &[stats.into()]);
+        }
+    }
+
+    /// What the session reached so far, as a plain object that can be stored
+    /// and read back after a restart: `{phase, code, bytes, total, error}`,
+    /// with `phase` the last `phase` event, `code` the code it allocated,
+    /// `bytes` and `total` the last `progress` and `error` the message the
+    /// session failed with. Missing values are `null`.
+    #[wasm_bindgen(getter)]
+    pub fn state(&self) -> Result<JsValue, JsValue> {
+        Ok(js::to_js(&*self.listeners.state.borrow())?)
+    }
+
+    /// Stops moving data until `resume()` is called. The connection to the
+    /// peer stays open, though a relay may drop it if the pause is long.
+    pub fn pause(&self) {
+        self.pause.set_paused(true);
+    }
+
+    pub fn resume(&self) {
+        self.pause.set_paused(false);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn paused(&self) -> bool {
+        self.pause.is_paused()
+    }
+
+    /// Aborts the running transfer. Once the peer has connected the mailbox
+    /// is released and closed, with the mood magic-wormhole picks for a
+    /// cancelled transfer; before that the rendezvous connection is dropped,
+    /// and the server frees the nameplate on its own. A closed session ends
+    /// with an `error` event.
+    pub fn close(&self) {
+        self.cancel.cancel();
+    }
+}
+
+impl WormholeSession {
+    pub(crate) fn registered(config: &ClientConfig, registry: &Rc<Registry>) -> Self {
+        let mut session = WormholeSession::new(config);
+        let id = registry.register(&session.cancel, &session.pause);
+        session.registration = Some((id, registry.clone()));
+        session
+    }
+
+    fn context(&self) -> TransferContext {
+        TransferContext {
+            events: self.listeners.clone(),
+            on_verifier: self.listeners.first("verifier"),
+            cancel: self.cancel.clone(),
+            pause: self.pause.clone(),
+            expected_sha256: None,
+        }
+    }
+}
+
+impl Drop for WormholeSession {
+    fn drop(&mut self) {
+        if let (Some(window), Some(closure)) = (web_sys::window(), &self.on_pagehide) {
+            let _ = window.remove_event_listener_with_callback("pagehide", closure.as_ref().unchecked_ref());
+        }
+        if let Some((id, registry)) = &self.registration {
+            registry.unregister(*id);
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! Byte counts crossing the JS boundary.
+//!
+//! Sizes are `u64` inside the crate. JS numbers represent integers exactly
+//! only up to `Number.MAX_SAFE_INTEGER` (2^53 - 1), which no file a browser
+//! can hold comes close to, so sizes we produce are passed out as numbers.
+//! Sizes declared by the peer are not bounded by anything, so they are
+//! passed out as `BigInt`.
+
+use wasm_bindgen::prelude::*;
+
+/// `Number.MAX_SAFE_INTEGER`.
+pub(crate) const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Checks that `value`, passed in from JS as `what`, is a byte count.
+pub(crate) fn from_js(value: f64, what: &str) -> Result<u64, JsValue> {
+    if value.is_finite() && value >= 0.0 && value.fract() == 0.0 && value <= MAX_SAFE_INTEGER as f64 {
+        Ok(value as u64)
+    } else {
+        Err(JsValue::from_str(&format!("{} must be a whole number of bytes, got {}", what, value)))
+    }
+}
+
+/// A size declared by the peer, as a `BigInt`.
+pub fn declared(size: u64) -> JsValue {
+    js_sys::BigInt::from(size).into()
+}
@@ -0,0 +1,282 @@
+use std::cell::RefCell;
+
+use wasm_bindgen::prelude::*;
+
+use crate::archive::Layout;
+use crate::TransitInfo;
+
+/// Throughput is sampled over windows of at least this length for the peak.
+const SAMPLE_WINDOW_MS: f64 = 1000.0;
+
+/// The time constant of the smoothed rate: older data counts for less, by a
+/// factor of e every this many milliseconds.
+const SMOOTHING_MS: f64 = 3000.0;
+
+/// How often progress is reported by default, in milliseconds.
+pub(crate) const DEFAULT_PROGRESS_INTERVAL_MS: f64 = 100.0;
+
+/// How much has to happen between two `progress` events, see
+/// `ClientConfig.set_progress_interval`. The last one is always reported.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ProgressInterval {
+    pub(crate) min_ms: f64,
+    pub(crate) min_bytes: u64,
+}
+
+impl Default for ProgressInterval {
+    fn default() -> Self {
+        ProgressInterval {
+            min_ms: DEFAULT_PROGRESS_INTERVAL_MS,
+            min_bytes: 0,
+        }
+    }
+}
+
+/// Where a running transfer is, as reported with `progress` events.
+#[derive(Clone, Debug)]
+pub struct Progress {
+    pub(crate) current: u64,
+    pub(crate) total: u64,
+    /// The rate over the last complete sample window.
+    pub(crate) bytes_per_second: f64,
+    /// An exponentially weighted average of the rate, which changes less
+    /// abruptly on bursty connections.
+    pub(crate) smoothed_bytes_per_second: f64,
+    /// The time left at the smoothed rate, once there is one.
+    pub(crate) eta_ms: Option<f64>,
+    /// The archive entry being transferred, when the file is an archive
+    /// whose entries were announced.
+    pub(crate) entry: Option<EntryProgress>,
+}
+
+/// The entry of an archive a transfer is at, see `Progress.entry`.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct EntryProgress {
+    pub(crate) name: String,
+    /// Of the entry, in archive order, and of all entries.
+    pub(crate) index: usize,
+    pub(crate) count: usize,
+    /// Bytes of this entry transferred so far, and its size.
+    pub(crate) current: u64,
+    pub(crate) total: u64,
+}
+
+impl Progress {
+    /// Bytes transferred so far.
+    pub fn current(&self) -> u64 {
+        self.current
+    }
+
+    /// The size of the file.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn bytes_per_second(&self) -> f64 {
+        self.bytes_per_second
+    }
+
+    pub fn smoothed_bytes_per_second(&self) -> f64 {
+        self.smoothed_bytes_per_second
+    }
+
+    pub fn eta_ms(&self) -> Option<f64> {
+        self.eta_ms
+    }
+
+    pub fn entry(&self) -> Option<&EntryProgress> {
+        self.entry.as_ref()
+    }
+}
+
+impl EntryProgress {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn current(&self) -> u64 {
+        self.current
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+}
+
+/// A summary of a completed transfer.
+#[wasm_bindgen]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct TransferStats {
+    bytes: u64,
+    elapsed_ms: f64,
+    average_bytes_per_second: f64,
+    peak_bytes_per_second: f64,
+    connection: String,
+    relay_url: String,
+}
+
+#[wasm_bindgen]
+impl TransferStats {
+    #[wasm_bindgen(getter)]
+    pub fn bytes(&self) -> f64 {
+        self.bytes as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn elapsed_ms(&self) -> f64 {
+        self.elapsed_ms
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn average_bytes_per_second(&self) -> f64 {
+        self.average_bytes_per_second
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn peak_bytes_per_second(&self) -> f64 {
+        self.peak_bytes_per_second
+    }
+
+    /// `"direct"` or `"relay"`, or `"unknown"` if no transit connection was
+    /// reported.
+    #[wasm_bindgen(getter)]
+    pub fn connection(&self) -> String {
+        self.connection.clone()
+    }
+
+    /// The first transit relay offered to the peer.
+    #[wasm_bindgen(getter)]
+    pub fn relay_url(&self) -> String {
+        self.relay_url.clone()
+    }
+}
+
+struct Samples {
+    bytes: u64,
+    window_start: f64,
+    window_bytes: u64,
+    rate: f64,
+    peak: f64,
+    last_update: f64,
+    smoothed: f64,
+    /// The time and bytes of the last reported progress.
+    reported: Option<(f64, u64)>,
+    connection: String,
+}
+
+/// Collects the numbers for `TransferStats` while a transfer runs.
+pub(crate) struct StatsRecorder {
+    start: f64,
+    relay_url: String,
+    interval: ProgressInterval,
+    layout: Option<Layout>,
+    samples: RefCell<Samples>,
+}
+
+impl StatsRecorder {
+    /// `layout` is that of the file, if it is an archive.
+    pub(crate) fn new(relay_url: &url::Url, interval: ProgressInterval, layout: Option<Layout>) -> Self {
+        let start = js_sys::Date::now();
+        StatsRecorder {
+            start,
+            relay_url: relay_url.to_string(),
+            interval,
+            layout,
+            samples: RefCell::new(Samples {
+                bytes: 0,
+                window_start: start,
+                window_bytes: 0,
+                rate: 0.0,
+                peak: 0.0,
+                last_update: start,
+                smoothed: 0.0,
+                reported: None,
+                connection: "unknown".to_owned(),
+            }),
+        }
+    }
+
+    pub(crate) fn connected(&self, info: &TransitInfo) {
+        self.samples.borrow_mut().connection = info.kind();
+    }
+
+    /// Records that `current` bytes moved, and returns the progress to
+    /// report if enough happened since it was last reported.
+    pub(crate) fn progress(&self, current: u64, total: u64) -> Option<Progress> {
+        let now = js_sys::Date::now();
+        let mut samples = self.samples.borrow_mut();
+
+        let since_update = now - samples.last_update;
+        if since_update > 0.0 {
+            let rate = current.saturating_sub(samples.bytes) as f64 * 1000.0 / since_update;
+            samples.smoothed = if samples.smoothed == 0.0 {
+                rate
+            } else {
+                let weight = 1.0 - (-since_update / SMOOTHING_MS).exp();
+                samples.smoothed + weight * (rate - samples.smoothed)
+            };
+            samples.last_update = now;
+        }
+        samples.bytes = current;
+
+        let elapsed = now - samples.window_start;
+        if elapsed >= SAMPLE_WINDOW_MS {
+            let rate = current.saturating_sub(samples.window_bytes) as f64 * 1000.0 / elapsed;
+            samples.rate = rate;
+            samples.peak = samples.peak.max(rate);
+            samples.window_start = now;
+            samples.window_bytes = current;
+        }
+
+        let due = match samples.reported {
+            None => true,
+            Some((time, bytes)) => {
+                current >= total
+                    || (now - time >= self.interval.min_ms && current.saturating_sub(bytes) >= self.interval.min_bytes)
+            }
+        };
+        if !due {
+            return None;
+        }
+        samples.reported = Some((now, current));
+
+        Some(Progress {
+            current,
+            total,
+            bytes_per_second: samples.rate,
+            smoothed_bytes_per_second: samples.smoothed,
+            eta_ms: Some(samples.smoothed)
+                .filter(|smoothed| *smoothed > 0.0)
+                .map(|smoothed| total.saturating_sub(current) as f64 * 1000.0 / smoothed),
+            entry: self.layout.as_ref().and_then(|layout| layout.locate(current, total)),
+        })
+    }
+
+    pub(crate) fn finish(&self) -> TransferStats {
+        let elapsed_ms = js_sys::Date::now() - self.start;
+        let samples = self.samples.borrow();
+        let average = if elapsed_ms > 0.0 {
+            samples.bytes as f64 * 1000.0 / elapsed_ms
+        } else {
+            0.0
+        };
+
+        TransferStats {
+            bytes: samples.bytes,
+            elapsed_ms,
+            average_bytes_per_second: average,
+            // Transfers shorter than one sample window have no better estimate.
+            peak_bytes_per_second: samples.peak.max(average),
+            connection: samples.connection.clone(),
+            relay_url: self.relay_url.clone(),
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Adapters between the streams of the Streams API and `futures::io`, used
+//! by the transfers but independent of them: `StreamReader` reads a
+//! `ReadableStream` as an `AsyncRead`, and `StreamWriter` writes to a
+//! `WritableStream` as an `AsyncWrite`.
+//!
+//! ```ignore
+//! let (readable, writable) = stream::pipe();
+//! let mut writer = StreamWriter::new(&writable);
+//! writer.write_all(b"hello").await?;
+//! writer.close().await?;
+//! ```
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::{AsyncRead, AsyncWrite};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::js::js_error;
+
+#[wasm_bindgen]
+extern {
+    /// A JS `ReadableStream` yielding `Uint8Array` chunks.
+    pub type ReadableStream;
+
+    /// `source` is an underlying source object, like `{start(controller)}`.
+    #[wasm_bindgen(constructor)]
+    pub(crate) fn new(source: &js_sys::Object) -> ReadableStream;
+
+    pub(crate) type ReadableStreamDefaultController;
+
+    #[wasm_bindgen(method, catch)]
+    pub(crate) fn enqueue(this: &ReadableStreamDefaultController, chunk: &JsValue) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch)]
+    pub(crate) fn close(this: &ReadableStreamDefaultController) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, js_name = getReader)]
+    fn get_reader(this: &ReadableStream) -> ReadableStreamDefaultReader;
+
+    type ReadableStreamDefaultReader;
+
+    #[wasm_bindgen(method)]
+    fn read(this: &ReadableStreamDefaultReader) -> js_sys::Promise;
+
+    /// A JS `WritableStream` accepting `Uint8Array` chunks.
+    pub type WritableStream;
+
+    #[wasm_bindgen(method, js_name = getWriter)]
+    fn get_writer(this: &WritableStream) -> WritableStreamDefaultWriter;
+
+    type WritableStreamDefaultWriter;
+
+    #[wasm_bindgen(method, getter)]
+    fn ready(this: &WritableStreamDefaultWriter) -> js_sys::Promise;
+
+    #[wasm_bindgen(method)]
+    fn write(this: &WritableStreamDefaultWriter, chunk: &js_sys::Uint8Array) -> js_sys::Promise;
+
+    #[wasm_bindgen(method)]
+    fn close(this: &WritableStreamDefaultWriter) -> js_sys::Promise;
+
+    #[wasm_bindgen(method)]
+    fn abort(this: &WritableStreamDefaultWriter) -> js_sys::Promise;
+
+    type TransformStream;
+
+    #[wasm_bindgen(constructor)]
+    fn new() -> TransformStream;
+
+    #[wasm_bindgen(method, getter)]
+    fn readable(this: &TransformStream) -> ReadableStream;
+
+    #[wasm_bindgen(method, getter)]
+    fn writable(this: &TransformStream) -> WritableStream;
+}
+
+/// The two ends of an identity `TransformStream`: what is written to the
+/// `WritableStream` can be read from the `ReadableStream`.
+pub fn pipe() -> (ReadableStream, WritableStream) {
+    let stream = TransformStream::new();
+    (stream.readable(), stream.writable())
+}
+
+/// Reads the chunks of a `ReadableStream` as an `AsyncRead`. The stream is
+/// locked to it.
+pub struct StreamReader {
+    reader: ReadableStreamDefaultReader,
+    pending: Option<JsFuture>,
+    chunk: Vec<u8>,
+    offset: usize,
+    done: bool,
+}
+
+impl StreamReader {
+    pub fn new(stream: &ReadableStream) -> Self {
+        StreamReader {
+            reader: stream.get_reader(),
+            pending: None,
+            chunk: Vec::new(),
+            offset: 0,
+            done: false,
+        }
+    }
+
+    pub fn from_blob(blob: &web_sys::Blob) -> Self {
+        Self::new(blob.stream().unchecked_ref())
+    }
+}
+
+impl AsyncRead for StreamReader {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        loop {
+            if self.offset < self.chunk.len() {
+                let len = buf.len().min(self.chunk.len() - self.offset);
+                buf[..len].copy_from_slice(&self.chunk[self.offset..self.offset + len]);
+                self.offset += len;
+                return Poll::Ready(Ok(len));
+            }
+
+            if self.done {
+                return Poll::Ready(Ok(0));
+            }
+
+            if self.pending.is_none() {
+                let read = JsFuture::from(self.reader.read());
+                self.pending = Some(read);
+            }
+
+            let result = match Pin::new(self.pending.as_mut().unwrap()).poll(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(result) => result,
+            };
+            self.pending = None;
+
+            let result = result.map_err(js_error)?;
+            let done = js_sys::Reflect::get(&result, &JsValue::from_str("done")).map_err(js_error)?;
+            if done.is_truthy() {
+                self.done = true;
+                continue;
+            }
+
+            let value = js_sys::Reflect::get(&result, &JsValue::from_str("value")).map_err(js_error)?;
+            let value = value.unchecked_into::<js_sys::Uint8Array>();
+            self.chunk.resize(value.length() as usize, 0);
+            value.copy_to(&mut self.chunk);
+            self.offset = 0;
+        }
+    }
+}
+
+/// Writes to a `WritableStream` as an `AsyncWrite`, locking the stream.
+///
+/// A write waits for the stream's `ready` promise, that is until its queue
+/// is below the high water mark, but not for the chunk to reach the sink,
+/// so the queuing strategy of the stream decides how far the writer runs
+/// ahead. Flushing waits for everything written to reach the sink. A
+/// failed write errors the stream, which the next write or flush reports.
+/// Dropping the writer before it is closed aborts the stream, so a reader
+/// does not mistake a failed transfer for a complete one.
+pub struct StreamWriter {
+    writer: WritableStreamDefaultWriter,
+    ready: Option<JsFuture>,
+    /// The last write; writes complete in order.
+    written: Option<JsFuture>,
+    closing: Option<JsFuture>,
+}
+
+impl StreamWriter {
+    pub fn new(stream: &WritableStream) -> Self {
+        StreamWriter {
+            writer: stream.get_writer(),
+            ready: None,
+            written: None,
+            closing: None,
+        }
+    }
+}
+
+impl AsyncWrite for StreamWriter {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        if self.ready.is_none() {
+            let ready = JsFuture::from(self.writer.ready());
+            self.ready = Some(ready);
+        }
+        let result = match Pin::new(self.ready.as_mut().unwrap()).poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(result) => result,
+        };
+        self.ready = None;
+        result.map_err(js_error)?;
+
+        let promise = self.writer.write(&js_sys::Uint8Array::from(buf));
+        self.written = Some(JsFuture::from(promise));
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let written = match self.written.as_mut() {
+            Some(written) => written,
+            None => return Poll::Ready(Ok(())),
+        };
+        let result = match Pin::new(written).poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(result) => result,
+        };
+        self.written = None;
+        Poll::Ready(result.map(|_| ()).map_err(js_error))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.as_mut().poll_flush(cx)?.is_pending() {
+            return Poll::Pending;
+        }
+        if self.closing.is_none() {
+            let promise = self.writer.close();
+            self.closing = Some(JsFuture::from(promise));
+        }
+        Pin::new(self.closing.as_mut().unwrap()).poll(cx).map(|result| result.map(|_| ()).map_err(js_error))
+    }
+}
+
+impl Drop for StreamWriter {
+    fn drop(&mut self) {
+        if self.closing.is_none() {
+            let _ = self.writer.abort();
+        }
+    }
+}
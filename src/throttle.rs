@@ -0,0 +1,88 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::{AsyncRead, AsyncWrite};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::timer;
+
+/// Limits the bytes read from or written to `inner` to a rate, by holding
+/// back reads and writes once they got ahead of it.
+pub(crate) struct Throttle<T> {
+    inner: T,
+    bytes_per_second: Option<u64>,
+    start: f64,
+    bytes: u64,
+    sleep: Option<JsFuture>,
+}
+
+impl<T> Throttle<T> {
+    /// Without `bytes_per_second`, everything passes through unchanged.
+    pub(crate) fn new(inner: T, bytes_per_second: Option<u64>) -> Self {
+        Throttle {
+            inner,
+            bytes_per_second,
+            start: js_sys::Date::now(),
+            bytes: 0,
+            sleep: None,
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// How many bytes may pass right now, waiting until it is at least one.
+    fn poll_budget(&mut self, cx: &mut Context<'_>) -> Poll<usize> {
+        let rate = match self.bytes_per_second {
+            Some(rate) if rate > 0 => rate as f64,
+            _ => return Poll::Ready(usize::MAX),
+        };
+        loop {
+            if let Some(sleep) = &mut self.sleep {
+                let _ = futures::ready!(Pin::new(sleep).poll(cx));
+                self.sleep = None;
+            }
+            let elapsed = js_sys::Date::now() - self.start;
+            let allowed = (rate * elapsed / 1000.0) as u64;
+            if allowed > self.bytes {
+                return Poll::Ready((allowed - self.bytes).min(usize::MAX as u64) as usize);
+            }
+            let wait = ((self.bytes + 1) as f64 * 1000.0 / rate - elapsed).ceil().max(1.0);
+            self.sleep = Some(JsFuture::from(timer::timeout(wait as i32)));
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Throttle<T> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+        let this = &mut *self;
+        let len = futures::ready!(this.poll_budget(cx)).min(buf.len());
+        let read = Pin::new(&mut this.inner).poll_read(cx, &mut buf[..len]);
+        if let Poll::Ready(Ok(n)) = read {
+            this.bytes += n as u64;
+        }
+        read
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Throttle<T> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        let this = &mut *self;
+        let len = futures::ready!(this.poll_budget(cx)).min(buf.len());
+        let written = Pin::new(&mut this.inner).poll_write(cx, &buf[..len]);
+        if let Poll::Ready(Ok(n)) = written {
+            this.bytes += n as u64;
+        }
+        written
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
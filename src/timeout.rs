@@ -0,0 +1,136 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::events::Events;
+use crate::timer;
+
+/// How often the watchdog checks for an expired timeout.
+const CHECK_INTERVAL_MS: i32 = 1000;
+
+/// The stage of a transfer a timeout applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timeout {
+    /// The sender waiting for a receiver to use the code.
+    Peer,
+    /// The receiver connecting with a code, including the key exchange.
+    KeyExchange,
+    /// Setting up the transit connection.
+    Transit,
+    /// No data moved for too long once the transfer was running.
+    Stalled,
+}
+
+impl Timeout {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Timeout::Peer => "peer",
+            Timeout::KeyExchange => "key-exchange",
+            Timeout::Transit => "transit",
+            Timeout::Stalled => "stalled",
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            Timeout::Peer => "Timed out waiting for the peer to use the code",
+            Timeout::KeyExchange => "Timed out connecting to the peer",
+            Timeout::Transit => "Timed out setting up the transit connection",
+            Timeout::Stalled => "The transfer stalled",
+        }
+    }
+}
+
+/// The limits set with `ClientConfig.set_timeouts`, in milliseconds.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Timeouts {
+    pub(crate) peer_ms: Option<i32>,
+    pub(crate) key_exchange_ms: Option<i32>,
+    pub(crate) transit_ms: Option<i32>,
+    pub(crate) stall_ms: Option<i32>,
+    /// When a `stalled` event is reported, see `ClientConfig.set_stall_warning`.
+    pub(crate) stall_warning_ms: Option<i32>,
+}
+
+/// Watches a running transfer for the transit and stall timeouts.
+pub(crate) struct Watchdog {
+    transit_ms: Option<i32>,
+    stall_ms: Option<i32>,
+    stall_warning_ms: Option<i32>,
+    start: f64,
+    connected: Cell<bool>,
+    last_activity: Cell<f64>,
+    /// Whether the current stall was reported yet.
+    warned: Cell<bool>,
+    expired: Cell<Option<Timeout>>,
+}
+
+impl Watchdog {
+    pub(crate) fn new(timeouts: &Timeouts) -> Rc<Self> {
+        let now = js_sys::Date::now();
+        Rc::new(Watchdog {
+            transit_ms: timeouts.transit_ms,
+            stall_ms: timeouts.stall_ms,
+            stall_warning_ms: timeouts.stall_warning_ms,
+            start: now,
+            connected: Cell::new(false),
+            last_activity: Cell::new(now),
+            warned: Cell::new(false),
+            expired: Cell::new(None),
+        })
+    }
+
+    pub(crate) fn connected(&self) {
+        self.connected.set(true);
+        self.activity();
+    }
+
+    pub(crate) fn activity(&self) {
+        self.last_activity.set(js_sys::Date::now());
+        self.warned.set(false);
+    }
+
+    /// The timeout that ended the transfer, if any.
+    pub(crate) fn expired(&self) -> Option<Timeout> {
+        self.expired.get()
+    }
+
+    /// Completes once a timeout has expired, for passing as the cancel
+    /// future of a transfer. Stalls are reported to `events` on the way.
+    pub(crate) async fn expiry(self: Rc<Self>, events: Rc<dyn Events>) {
+        if self.transit_ms.is_none() && self.stall_ms.is_none() && self.stall_warning_ms.is_none() {
+            return futures::future::pending().await;
+        }
+        loop {
+            timer::sleep(CHECK_INTERVAL_MS).await;
+            let now = js_sys::Date::now();
+            let exceeded = |since: f64, limit: Option<i32>| limit.is_some_and(|limit| now - since > limit as f64);
+            if self.connected.get() && !self.warned.get() && exceeded(self.last_activity.get(), self.stall_warning_ms) {
+                self.warned.set(true);
+                events.stalled(now - self.last_activity.get());
+            }
+            let expired = if self.connected.get() {
+                Some(Timeout::Stalled).filter(|_| exceeded(self.last_activity.get(), self.stall_ms))
+            } else {
+                Some(Timeout::Transit).filter(|_| exceeded(self.start, self.transit_ms))
+            };
+            if expired.is_some() {
+                self.expired.set(expired);
+                return;
+            }
+        }
+    }
+}
+
+/// Counts down `ms` milliseconds, reporting the remaining time to `events`
+/// about once a second.
+pub(crate) async fn countdown(ms: i32, events: &dyn Events) {
+    let end = js_sys::Date::now() + ms as f64;
+    loop {
+        let remaining = end - js_sys::Date::now();
+        if remaining <= 0.0 {
+            return;
+        }
+        events.code_countdown(remaining);
+        timer::sleep(remaining.min(CHECK_INTERVAL_MS as f64).ceil() as i32).await;
+    }
+}
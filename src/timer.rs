@@ -0,0 +1,84 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::future::Either;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+#[wasm_bindgen]
+extern {
+    // The global `setTimeout`, available in windows and workers alike.
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(handler: &js_sys::Function, timeout: i32) -> JsValue;
+}
+
+/// A promise that rejects after `ms` milliseconds, for racing against other
+/// promises.
+pub(crate) fn deadline(ms: i32) -> js_sys::Promise {
+    js_sys::Promise::new(&mut |_, reject| {
+        set_timeout(&reject, ms);
+    })
+}
+
+/// A promise that resolves after `ms` milliseconds.
+pub(crate) fn timeout(ms: i32) -> js_sys::Promise {
+    js_sys::Promise::new(&mut |resolve, _| {
+        set_timeout(&resolve, ms);
+    })
+}
+
+/// Resolves in a task of its own, queued behind whatever the browser has
+/// queued already, through a message to itself. Unlike `timeout(0)`, this
+/// is not clamped to once a second or less in hidden tabs.
+pub(crate) fn next_task() -> NextTask {
+    let channel = web_sys::MessageChannel::new().ok();
+    let promise = js_sys::Promise::new(&mut |resolve, _| match &channel {
+        Some(channel) => {
+            channel.port1().set_onmessage(Some(&resolve));
+            if channel.port2().post_message(&JsValue::UNDEFINED).is_err() {
+                set_timeout(&resolve, 0);
+            }
+        }
+        None => {
+            set_timeout(&resolve, 0);
+        }
+    });
+    NextTask {
+        promise: JsFuture::from(promise),
+        _channel: channel,
+    }
+}
+
+/// See `next_task`. Holds on to the channel until the message arrived.
+pub(crate) struct NextTask {
+    promise: JsFuture,
+    _channel: Option<web_sys::MessageChannel>,
+}
+
+impl Future for NextTask {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let _ = futures::ready!(Pin::new(&mut self.promise).poll(cx));
+        Poll::Ready(())
+    }
+}
+
+pub(crate) async fn sleep(ms: i32) {
+    let _ = JsFuture::from(timeout(ms)).await;
+}
+
+pub(crate) struct TimedOut;
+
+/// Runs `future` for at most `ms` milliseconds, or without a limit.
+pub(crate) async fn within<F: Future>(ms: Option<i32>, future: F) -> Result<F::Output, TimedOut> {
+    let ms = match ms {
+        Some(ms) => ms,
+        None => return Ok(future.await),
+    };
+    match futures::future::select(Box::pin(future), JsFuture::from(timeout(ms))).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(TimedOut),
+    }
+}
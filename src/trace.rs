@@ -0,0 +1,172 @@
+//! Protocol traces, see `ClientConfig.set_trace`.
+//!
+//! Every event of a traced transfer is passed to the trace callback as an
+//! object like `{t: 1532.5, direction: "in", phase: "key-confirmed",
+//! event: "offer", filename: "a.txt", filesize: 123}`. `t` counts the
+//! milliseconds since the transfer started, `direction` is `in` for what
+//! the peer or server sent, `out` for what this side sent and `local` for
+//! everything else. The messages magic-wormhole exchanges with the
+//! rendezvous server are logged by the library itself, at the `debug` and
+//! `trace` levels of the `magic_wormhole` target, see `init_with_options`.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use magic_wormhole::{Code, Wormhole};
+use serde_json::{json, Value};
+use wasm_bindgen::prelude::*;
+
+use crate::events::Events;
+use crate::js;
+use crate::phase::Phase;
+use crate::stats::Progress;
+use crate::timeout::Timeout;
+use crate::{TransitInfo, WormholeCode};
+
+/// Which way a traced message went.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    In,
+    Out,
+    Local,
+}
+
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::In => "in",
+            Direction::Out => "out",
+            Direction::Local => "local",
+        }
+    }
+}
+
+/// Passes the events of a transfer on to `inner`, tracing each of them.
+pub(crate) struct Traced {
+    inner: Rc<dyn Events>,
+    sink: js_sys::Function,
+    start: f64,
+    phase: Cell<Option<Phase>>,
+    /// Which way the file data flows.
+    data: Direction,
+}
+
+impl Traced {
+    pub(crate) fn new(inner: Rc<dyn Events>, sink: js_sys::Function, data: Direction) -> Self {
+        Traced {
+            inner,
+            sink,
+            start: js_sys::Date::now(),
+            phase: Cell::new(None),
+            data,
+        }
+    }
+}
+
+impl Events for Traced {
+    fn status(&self, message: &str) {
+        self.trace(Direction::Local, "status", json!({ "message": message }));
+        self.inner.status(message);
+    }
+
+    fn phase(&self, phase: Phase) {
+        self.phase.set(Some(phase));
+        self.trace(Direction::Local, "phase", json!({}));
+        self.inner.phase(phase);
+    }
+
+    fn welcome(&self, message: &str) {
+        self.trace(Direction::In, "welcome", json!({ "message": message }));
+        self.inner.welcome(message);
+    }
+
+    fn code(&self, code: &Code) {
+        let parts = WormholeCode::from(code);
+        self.trace(Direction::In, "code", json!({
+            "nameplate": parts.nameplate(),
+            "words": parts.words().length(),
+        }));
+        self.inner.code(code);
+    }
+
+    fn alternate_code(&self, code: &Code) {
+        let parts = WormholeCode::from(code);
+        self.trace(Direction::In, "alternate-code", json!({
+            "nameplate": parts.nameplate(),
+            "words": parts.words().length(),
+        }));
+        self.inner.alternate_code(code);
+    }
+
+    fn code_countdown(&self, remaining_ms: f64) {
+        self.inner.code_countdown(remaining_ms);
+    }
+
+    fn code_expired(&self) {
+        self.trace(Direction::Local, "code-expired", json!({}));
+        self.inner.code_expired();
+    }
+
+    fn connected(&self, wormhole: &Wormhole) {
+        self.trace(Direction::In, "app-versions", json!({ "app_versions": wormhole.peer_version() }));
+        self.inner.connected(wormhole);
+    }
+
+    fn transit(&self, info: TransitInfo) {
+        self.trace(Direction::Local, "transit", json!({
+            "kind": info.kind(),
+            "relay_url": info.relay_url(),
+        }));
+        self.inner.transit(info);
+    }
+
+    fn stalled(&self, idle_ms: f64) {
+        self.trace(Direction::Local, "stalled", json!({ "idle_ms": idle_ms }));
+        self.inner.stalled(idle_ms);
+    }
+
+    fn network(&self, online: bool) {
+        self.trace(Direction::Local, if online { "online" } else { "offline" }, json!({}));
+        self.inner.network(online);
+    }
+
+    fn wrong_code(&self) {
+        self.trace(Direction::In, "key-confirmation-failed", json!({}));
+        self.inner.wrong_code();
+    }
+
+    fn timed_out(&self, timeout: Timeout) {
+        self.trace(Direction::Local, "timeout", json!({ "stage": timeout.as_str() }));
+        self.inner.timed_out(timeout);
+    }
+
+    fn rejected(&self, message: &str) {
+        self.trace(Direction::In, "error", json!({ "message": message }));
+        self.inner.rejected(message);
+    }
+
+    fn progress(&self, progress: Progress) {
+        self.trace(self.data, "data", json!({ "current": progress.current, "total": progress.total }));
+        self.inner.progress(progress);
+    }
+
+    fn error(&self, message: &str) {
+        self.trace(Direction::Local, "failed", json!({ "message": message }));
+        self.inner.error(message);
+    }
+
+    fn trace(&self, direction: Direction, event: &str, fields: Value) {
+        let mut record = json!({
+            "t": js_sys::Date::now() - self.start,
+            "direction": direction.as_str(),
+            "phase": self.phase.get().map(Phase::as_str),
+            "event": event,
+        });
+        if let (Some(record), Value::Object(fields)) = (record.as_object_mut(), fields) {
+            record.extend(fields);
+        }
+        if let Err(e) = self.sink.call1(&JsValue::NULL, &js::to_js_or_undefined(&record)) {
+            console_log!("Error in trace callback: {:?}", e);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use magic_wormhole::transit;
+use wasm_bindgen::prelude::*;
+
+/// How the transit connection to the peer was established.
+///
+/// Only the connection that won is known here: the peer's hints and the
+/// attempts to connect to them stay inside magic-wormhole. To see them, log
+/// `magic_wormhole::transit` at `debug`, e.g. with
+/// `init_with_options("warn", ["magic_wormhole::transit=debug"])`.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct TransitInfo {
+    kind: String,
+    relay_name: Option<String>,
+    relay_url: Option<String>,
+}
+
+impl TransitInfo {
+    /// `relay_url` is the first relay this side offered; it is only reported
+    /// for relayed connections. magic-wormhole does not say which relay it
+    /// connected through.
+    pub(crate) fn new(info: &transit::TransitInfo, relay_url: &str) -> Self {
+        match &info.conn_type {
+            transit::ConnectionType::Direct => TransitInfo {
+                kind: "direct".to_owned(),
+                relay_name: None,
+                relay_url: None,
+            },
+            transit::ConnectionType::Relay { name } => TransitInfo {
+                kind: "relay".to_owned(),
+                relay_name: name.clone(),
+                relay_url: Some(relay_url.to_owned()),
+            },
+            _ => TransitInfo {
+                kind: "unknown".to_owned(),
+                relay_name: None,
+                relay_url: None,
+            },
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl TransitInfo {
+    /// `"direct"` or `"relay"`, or `"unknown"` for kinds of connection
+    /// added to magic-wormhole since.
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        self.kind.clone()
+    }
+
+    /// The name the relay announced, if any.
+    #[wasm_bindgen(getter)]
+    pub fn relay_name(&self) -> Option<String> {
+        self.relay_name.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn relay_url(&self) -> Option<String> {
+        self.relay_url.clone()
+    }
+}
@@ -0,0 +1,210 @@
+//! TypeScript declarations for the parts of the API that cross the boundary
+//! as plain JS values: callbacks, event listeners and worker messages.
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_DECLARATIONS: &'static str = r#"
+/** A step of a transfer, see the `phase` event. */
+export type Phase =
+    | "code-allocated"
+    | "peer-connected"
+    | "key-confirmed"
+    | "transit-connecting"
+    | "transit-connected"
+    | "transferring"
+    | "verifying"
+    | "done";
+
+/** The stage a timeout applies to, see `ClientConfig.set_timeouts`. */
+export type TimeoutStage = "peer" | "key-exchange" | "transit" | "stalled";
+
+/** Resolving to `false` rejects the session. */
+export type VerifierCallback = (verifier: Verifier) => boolean | void | Promise<boolean | void>;
+
+/** Resolving to `false` or a reason rejects the offer, to entry names of an archive accepts only those. */
+export type OfferCallback = (filename: string, filesize: bigint, description: string | undefined, offer: Offer) => boolean | string | string[] | void | Promise<boolean | string | string[] | void>;
+
+/** The file of an archive a transfer is at, see the `progress` event. */
+export interface EntryProgress {
+    name: string;
+    index: number;
+    count: number;
+    current: number;
+    total: number;
+}
+
+/** An entry of `Offer.entries`. */
+export interface OfferEntry {
+    name: string;
+    size: bigint;
+    kind: "file" | "directory";
+}
+
+/** Resolving to `false` cancels the transfer. */
+export type PreviewCallback = (data: Uint8Array, filename: string, filesize: bigint) => boolean | void | Promise<boolean | void>;
+
+/** Returns the URL to connect to for the relay at `url`. */
+export type RelayAuthCallback = (url: string) => string | Promise<string>;
+
+/** Receives the records logged after `init_with_options`. */
+export type LogCallback = (level: "ERROR" | "WARN" | "INFO" | "DEBUG" | "TRACE", target: string, message: string) => void;
+
+/** A step of a traced transfer, see `ClientConfig.set_trace`. */
+export interface TraceRecord {
+    /** Milliseconds since the transfer started. */
+    t: number;
+    /** `in` for what the peer or server sent, `out` for what this side sent. */
+    direction: "in" | "out" | "local";
+    phase: Phase | null;
+    event: string;
+    [field: string]: any;
+}
+
+export type TraceCallback = (record: TraceRecord) => void;
+
+export interface ErrorReport {
+    kind: "panic" | "error";
+    message: string;
+    backtrace: string;
+    phase: Phase | null;
+}
+
+export type ErrorReporter = (report: ErrorReport) => void;
+
+export type AppVersions = { [key: string]: any };
+
+/** The options of `ClientConfig.from_js`. Missing ones take the defaults. */
+export interface ClientOptions {
+    appid?: string;
+    /** Rendezvous servers, tried in order. */
+    rendezvous?: string | string[];
+    /** Transit relays, raced against each other. */
+    transit?: string | string[];
+    /** How many words allocated codes have. */
+    codeLength?: number;
+    requireTls?: boolean;
+}
+
+export interface WormholeSessionEvents {
+    "status": (message: string) => void;
+    "phase": (phase: Phase) => void;
+    "welcome": (message: string) => void;
+    "code": (code: WormholeCode) => void;
+    "alternate-code": (code: WormholeCode) => void;
+    "countdown": (remainingMs: number) => void;
+    "expired": () => void;
+    "verifier": VerifierCallback;
+    "connected": (appVersions: AppVersions, key: SessionKey) => void;
+    "transit": (info: TransitInfo) => void;
+    "progress": (current: number, total: number, bytesPerSecond: number, smoothedBytesPerSecond: number, etaMs?: number, entry?: EntryProgress) => void;
+    "stalled": (idleMs: number) => void;
+    "offline": () => void;
+    "resumed": () => void;
+    "done": (result: TransferStats | ReceiveResult) => void;
+    "wrong-code": () => void;
+    "timeout": (stage: TimeoutStage, message: string) => void;
+    "rejected": (message: string) => void;
+    "error": (message: string) => void;
+}
+
+/** An event from `WormholeSession.events()`. */
+export type WormholeSessionEvent = {
+    [K in keyof WormholeSessionEvents]: { type: K; args: Parameters<WormholeSessionEvents[K]> };
+}[keyof WormholeSessionEvents];
+
+export interface WormholeSession {
+    on<K extends keyof WormholeSessionEvents>(event: K, callback: WormholeSessionEvents[K]): void;
+    events(): ReadableStream<WormholeSessionEvent>;
+}
+
+/** A message posted to the worker, see `ClientConfig.handle_message`. */
+export type WorkerRequest =
+    | { type: "send"; file: File }
+    | { type: "send-bytes"; name: string; data: ArrayBuffer }
+    | { type: "receive"; code: string; sha256?: string }
+    | { type: "receive-opfs"; code: string; name?: string; sha256?: string };
+
+/** A message posted back by the worker. */
+export type WorkerEvent =
+    | { type: "status"; message: string }
+    | { type: "phase"; phase: Phase }
+    | { type: "welcome"; message: string }
+    | { type: "code"; code: string; nameplate: string; words: string[] }
+    | { type: "alternate-code"; code: string; nameplate: string; words: string[] }
+    | { type: "countdown"; remaining_ms: number }
+    | { type: "expired" }
+    | { type: "connected"; app_versions: AppVersions; sas: string; emoji: string }
+    | { type: "transit"; kind: "direct" | "relay" | "unknown"; relay_url?: string }
+    | { type: "progress"; current: number; total: number; bytes_per_second: number; smoothed_bytes_per_second: number; eta_ms?: number; entry?: EntryProgress }
+    | { type: "stalled"; idle_ms: number }
+    | { type: "offline" }
+    | { type: "resumed" }
+    | { type: "wrong-code" }
+    | { type: "timeout"; stage: TimeoutStage; message: string }
+    | { type: "rejected"; message: string }
+    | { type: "error"; message: string }
+    | { type: "sent"; stats: object }
+    | {
+          type: "received";
+          filename: string;
+          raw_filename: string;
+          filesize: number;
+          mime_type: string;
+          last_modified?: number;
+          description?: string;
+          sha256: string;
+          data: ArrayBuffer;
+          stats: object;
+      }
+    | {
+          type: "saved";
+          filename: string;
+          raw_filename: string;
+          filesize: number;
+          mime_type: string;
+          last_modified?: number;
+          description?: string;
+          sha256: string;
+          stats: object;
+      };
+"#;
+
+#[wasm_bindgen]
+extern {
+    #[wasm_bindgen(typescript_type = "WorkerRequest")]
+    pub type WorkerRequest;
+
+    #[wasm_bindgen(typescript_type = "AppVersions")]
+    pub type AppVersions;
+
+    #[wasm_bindgen(typescript_type = "ClientOptions")]
+    pub type ClientOptions;
+
+    #[wasm_bindgen(typescript_type = "ArchiveEntry[]")]
+    pub type ArchiveEntries;
+
+    #[wasm_bindgen(typescript_type = "OfferEntry[]")]
+    pub type OfferEntries;
+
+    #[wasm_bindgen(typescript_type = "VerifierCallback")]
+    pub type VerifierCallback;
+
+    #[wasm_bindgen(typescript_type = "OfferCallback")]
+    pub type OfferCallback;
+
+    #[wasm_bindgen(typescript_type = "PreviewCallback")]
+    pub type PreviewCallback;
+
+    #[wasm_bindgen(typescript_type = "RelayAuthCallback")]
+    pub type RelayAuthCallback;
+
+    #[wasm_bindgen(typescript_type = "LogCallback")]
+    pub type LogCallback;
+
+    #[wasm_bindgen(typescript_type = "TraceCallback")]
+    pub type TraceCallback;
+
+    #[wasm_bindgen(typescript_type = "ErrorReporter")]
+    pub type ErrorReporter;
+}
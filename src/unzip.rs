@@ -0,0 +1,264 @@
+//! Extracting a zip archive below a directory while it is received, for
+//! `ClientConfig.receive_directory`.
+//!
+//! Entries are read one local header at a time and each is written to its
+//! file as its data arrives, so no more than a chunk of the archive is held
+//! in memory. That needs the size of every entry ahead of its data, which
+//! the archives of `archive::ZipStream` and of most other zip writers have;
+//! entries that only give it in a data descriptor afterwards cannot be
+//! extracted this way. The central directory at the end is skipped.
+
+use std::io::Write;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use flate2::write::DeflateDecoder;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::archive::{
+    CENTRAL_HEADER_SIGNATURE, DATA_DESCRIPTOR_SIGNATURE, DEFLATED, END_RECORD_SIGNATURE, FLAG_DATA_DESCRIPTOR,
+    LOCAL_HEADER_LEN, LOCAL_HEADER_SIGNATURE, STORED, ZIP64_EXTRA_ID,
+};
+use crate::directory::{self, FileSystemDirectoryHandle};
+use crate::extract::{malformed, u16_at, u32_at, u64_at, CHUNK_LEN};
+use crate::offer::Selection;
+use crate::stream::{StreamWriter, WritableStream};
+
+fn read_error(error: std::io::Error) -> JsValue {
+    JsValue::from_str(&format!("Error receiving the archive: {}", error))
+}
+
+/// A local file header, with the sizes from its zip64 extra field if it
+/// has one.
+struct LocalHeader {
+    name: String,
+    flags: u16,
+    method: u16,
+    crc: u32,
+    compressed_size: u64,
+    size: u64,
+    zip64: bool,
+}
+
+impl LocalHeader {
+    /// Reads the header after its signature.
+    async fn read<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self, JsValue> {
+        let mut fixed = [0; LOCAL_HEADER_LEN as usize - 4];
+        reader.read_exact(&mut fixed).await.map_err(read_error)?;
+        let mut name = vec![0; u16_at(&fixed, 22)? as usize];
+        reader.read_exact(&mut name).await.map_err(read_error)?;
+        let mut extra = vec![0; u16_at(&fixed, 24)? as usize];
+        reader.read_exact(&mut extra).await.map_err(read_error)?;
+
+        let mut header = LocalHeader {
+            name: String::from_utf8_lossy(&name).into_owned(),
+            flags: u16_at(&fixed, 2)?,
+            method: u16_at(&fixed, 4)?,
+            crc: u32_at(&fixed, 10)?,
+            compressed_size: u32_at(&fixed, 14)? as u64,
+            size: u32_at(&fixed, 18)? as u64,
+            zip64: false,
+        };
+        // The zip64 field holds the sizes that did not fit, in this order
+        let mut field = 0;
+        while field + 4 <= extra.len() {
+            let id = u16_at(&extra, field)?;
+            let len = u16_at(&extra, field + 2)? as usize;
+            if id == ZIP64_EXTRA_ID {
+                let mut value = field + 4;
+                for target in [&mut header.size, &mut header.compressed_size].iter_mut() {
+                    if **target == 0xffff_ffff {
+                        **target = u64_at(&extra, value)?;
+                        value += 8;
+                    }
+                }
+                header.zip64 = true;
+            }
+            field += 4 + len;
+        }
+        Ok(header)
+    }
+
+    fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    fn has_data_descriptor(&self) -> bool {
+        self.flags & FLAG_DATA_DESCRIPTOR != 0
+    }
+
+    /// Whether the sizes can be relied on before the data: with a data
+    /// descriptor, zero stands for sizes that only follow the data.
+    fn sizes_known(&self) -> bool {
+        !self.has_data_descriptor() || self.compressed_size != 0 || self.is_dir()
+    }
+}
+
+/// Reads the data descriptor after an entry, with or without its
+/// signature, and returns the CRC-32 in it.
+async fn read_data_descriptor<R: AsyncRead + Unpin>(reader: &mut R, zip64: bool) -> Result<u32, JsValue> {
+    let mut crc = [0; 4];
+    reader.read_exact(&mut crc).await.map_err(read_error)?;
+    if u32_at(&crc, 0)? == DATA_DESCRIPTOR_SIGNATURE {
+        reader.read_exact(&mut crc).await.map_err(read_error)?;
+    }
+    let mut sizes = [0; 16];
+    let sizes = &mut sizes[..if zip64 { 16 } else { 8 }];
+    reader.read_exact(sizes).await.map_err(read_error)?;
+    u32_at(&crc, 0)
+}
+
+/// Reads the data of the entry `header` and writes it, inflated if it is
+/// compressed, to `writer`. Returns the CRC-32 of what was written.
+async fn copy_entry<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(reader: &mut R, header: &LocalHeader, writer: &mut W) -> Result<u32, JsValue> {
+    let mut decoder = match header.method {
+        STORED => None,
+        DEFLATED => Some(DeflateDecoder::new(Vec::new())),
+        method => return Err(JsValue::from_str(&format!("{} uses compression method {}, which is not supported", header.name, method))),
+    };
+    let write_error = |e: std::io::Error| JsValue::from_str(&format!("Error writing {}: {}", header.name, e));
+    let inflate_error = |e: std::io::Error| JsValue::from_str(&format!("Error inflating {}: {}", header.name, e));
+
+    let mut crc = flate2::Crc::new();
+    let mut written = 0;
+    let mut chunk = vec![0; CHUNK_LEN];
+    let mut remaining = header.compressed_size;
+    loop {
+        let len = remaining.min(CHUNK_LEN as u64) as usize;
+        reader.read_exact(&mut chunk[..len]).await.map_err(read_error)?;
+        remaining -= len as u64;
+        let data = match decoder.as_mut() {
+            Some(decoder) => {
+                decoder.write_all(&chunk[..len]).map_err(inflate_error)?;
+                if remaining == 0 {
+                    decoder.try_finish().map_err(inflate_error)?;
+                }
+                std::mem::take(decoder.get_mut())
+            }
+            None => chunk[..len].to_vec(),
+        };
+        written += data.len() as u64;
+        if written > header.size {
+            return Err(malformed(&format!("{} is larger than its header says", header.name)));
+        }
+        crc.update(&data);
+        writer.write_all(&data).await.map_err(write_error)?;
+        if remaining == 0 {
+            break;
+        }
+    }
+    if written != header.size {
+        return Err(malformed(&format!("{} is smaller than its header says", header.name)));
+    }
+    Ok(crc.sum())
+}
+
+/// Reads past the data of the entry `header`.
+async fn skip_entry<R: AsyncRead + Unpin>(reader: &mut R, header: &LocalHeader) -> Result<(), JsValue> {
+    let skipped = futures::io::copy((&mut *reader).take(header.compressed_size), &mut futures::io::sink())
+        .await
+        .map_err(read_error)?;
+    if skipped != header.compressed_size {
+        return Err(malformed(&format!("{} is cut off", header.name)));
+    }
+    Ok(())
+}
+
+/// A reader that notes whether reading failed, as opposed to the data
+/// ending early.
+struct Watched<'a, R> {
+    reader: &'a mut R,
+    failed: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for Watched<'_, R> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+        let result = Pin::new(&mut *self.reader).poll_read(cx, buf);
+        if let Poll::Ready(Err(_)) = result {
+            self.failed = true;
+        }
+        result
+    }
+}
+
+/// Extracts the entries of the archive read from `reader` below
+/// `directory`, all of them or those in `selection`, and calls
+/// `on_progress` with `directory::report_written` as each one is written.
+/// `count` is the number of files to be written, if the sender announced
+/// its entries. Entries whose path leaves `directory` are skipped. Returns
+/// how many files were written, or `None` if reading the archive failed:
+/// the transfer writing it was aborted and reports why itself.
+///
+/// Otherwise the reader is read to its end even if extracting fails, so
+/// that the transfer writing to it can complete.
+pub(crate) async fn extract<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    directory: &FileSystemDirectoryHandle,
+    selection: Option<&Selection>,
+    count: Option<usize>,
+    on_progress: Option<&js_sys::Function>,
+) -> Option<Result<u32, JsValue>> {
+    let mut watched = Watched { reader, failed: false };
+    let result = extract_entries(&mut watched, directory, selection, count, on_progress).await;
+    if watched.failed {
+        return None;
+    }
+    let _ = futures::io::copy(watched.reader, &mut futures::io::sink()).await;
+    Some(result)
+}
+
+async fn extract_entries<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    directory: &FileSystemDirectoryHandle,
+    selection: Option<&Selection>,
+    count: Option<usize>,
+    on_progress: Option<&js_sys::Function>,
+) -> Result<u32, JsValue> {
+    let mut written = 0;
+    loop {
+        let mut signature = [0; 4];
+        reader.read_exact(&mut signature).await.map_err(read_error)?;
+        match u32_at(&signature, 0)? {
+            LOCAL_HEADER_SIGNATURE => (),
+            CENTRAL_HEADER_SIGNATURE | END_RECORD_SIGNATURE => return Ok(written),
+            _ => return Err(malformed("an entry does not start with a local header")),
+        }
+
+        let header = LocalHeader::read(reader).await?;
+        if !header.sizes_known() {
+            return Err(malformed(&format!("the size of {} only follows its data", header.name)));
+        }
+        let picked = !header.is_dir() && selection.is_none_or(|selection| selection.contains(&header.name));
+        let file = if picked { directory::create_file(directory, &header.name).await? } else { None };
+
+        let crc = match &file {
+            Some(file) => {
+                let writable = JsFuture::from(file.create_writable()).await?;
+                let mut writer = StreamWriter::new(&writable.unchecked_into::<WritableStream>());
+                let crc = copy_entry(reader, &header, &mut writer).await?;
+                writer.close().await.map_err(|e| JsValue::from_str(&format!("Error writing {}: {}", header.name, e)))?;
+                Some(crc)
+            }
+            None => {
+                skip_entry(reader, &header).await?;
+                None
+            }
+        };
+        let expected_crc = if header.has_data_descriptor() {
+            read_data_descriptor(reader, header.zip64).await?
+        } else {
+            header.crc
+        };
+        if crc.is_some_and(|crc| crc != expected_crc) {
+            return Err(malformed(&format!("the checksum of {} does not match", header.name)));
+        }
+
+        if file.is_some() {
+            directory::report_written(on_progress, &header.name, written as usize, count);
+            written += 1;
+        }
+    }
+}
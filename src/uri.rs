@@ -0,0 +1,109 @@
+//! `wormhole-transfer:` URIs, as used in QR codes and deep links by other
+//! Magic Wormhole clients:
+//!
+//! `wormhole-transfer:4-hurricane-equipment?rendezvous=ws%3A%2F%2Frelay.example%3A4000%2Fv1`
+//!
+//! The code takes the place of the path. The optional `rendezvous`
+//! parameter overrides the rendezvous server, `role` is `follower` (the
+//! default, meaning the reader of the URI receives) or `leader`, and
+//! `version` must be `0` if present.
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use wasm_bindgen::prelude::*;
+
+const SCHEME: &str = "wormhole-transfer";
+
+/// Characters of a code that are kept as they are.
+const CODE: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-');
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WormholeTransferUri {
+    code: String,
+    rendezvous_url: Option<String>,
+    leader: bool,
+}
+
+#[wasm_bindgen]
+impl WormholeTransferUri {
+    #[wasm_bindgen(constructor)]
+    pub fn new(code: String, rendezvous_url: Option<String>) -> WormholeTransferUri {
+        WormholeTransferUri {
+            code,
+            rendezvous_url,
+            leader: false,
+        }
+    }
+
+    pub fn parse(uri: &str) -> Result<WormholeTransferUri, JsValue> {
+        let invalid = |reason: &str| JsValue::from_str(&format!("Invalid wormhole-transfer URI ({}): {}", reason, uri));
+        let url = url::Url::parse(uri).map_err(|e| invalid(&e.to_string()))?;
+        if url.scheme() != SCHEME {
+            return Err(invalid("wrong scheme"));
+        }
+
+        let code = percent_decode_str(url.path())
+            .decode_utf8()
+            .map_err(|_| invalid("code is not UTF-8"))?
+            .into_owned();
+        if code.is_empty() {
+            return Err(invalid("missing code"));
+        }
+
+        let mut parsed = WormholeTransferUri::new(code, None);
+        for (key, value) in url.query_pairs() {
+            match &*key {
+                "version" if value != "0" => return Err(invalid("unsupported version")),
+                "rendezvous" => parsed.rendezvous_url = Some(value.into_owned()),
+                "role" => match &*value {
+                    "leader" => parsed.leader = true,
+                    "follower" => parsed.leader = false,
+                    _ => return Err(invalid("unknown role")),
+                },
+                _ => (),
+            }
+        }
+        Ok(parsed)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn code(&self) -> String {
+        self.code.clone()
+    }
+
+    /// The rendezvous server to use instead of the configured one.
+    #[wasm_bindgen(getter)]
+    pub fn rendezvous_url(&self) -> Option<String> {
+        self.rendezvous_url.clone()
+    }
+
+    /// Whether the reader of the URI is supposed to send rather than receive.
+    #[wasm_bindgen(getter)]
+    pub fn leader(&self) -> bool {
+        self.leader
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_leader(&mut self, leader: bool) {
+        self.leader = leader;
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_uri_string(&self) -> String {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        if let Some(rendezvous_url) = &self.rendezvous_url {
+            query.append_pair("rendezvous", rendezvous_url);
+        }
+        if self.leader {
+            query.append_pair("role", "leader");
+        }
+        let query = query.finish();
+
+        let mut uri = format!("{}:{}", SCHEME, utf8_percent_encode(&self.code, CODE));
+        if !query.is_empty() {
+            uri.push('?');
+            uri.push_str(&query);
+        }
+        uri
+    }
+}
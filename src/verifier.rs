@@ -0,0 +1,104 @@
+use magic_wormhole::Wormhole;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Words used to render the verifier as a short authentication string.
+/// Every word encodes six bits of the verifier.
+const SAS_WORDS: [&str; 64] = [
+    "dog", "cat", "lion", "horse", "unicorn", "pig", "elephant", "rabbit",
+    "panda", "rooster", "penguin", "turtle", "fish", "octopus", "butterfly", "flower",
+    "tree", "cactus", "mushroom", "globe", "moon", "cloud", "fire", "banana",
+    "apple", "strawberry", "corn", "pizza", "cake", "heart", "smiley", "robot",
+    "hat", "glasses", "spanner", "santa", "thumbs up", "umbrella", "hourglass", "clock",
+    "gift", "light bulb", "book", "pencil", "paperclip", "scissors", "lock", "key",
+    "hammer", "telephone", "flag", "train", "bicycle", "aeroplane", "rocket", "trophy",
+    "ball", "guitar", "trumpet", "bell", "anchor", "headphones", "folder", "pin",
+];
+
+/// The emoji for `SAS_WORDS`, in the same order. Words and emoji are those
+/// of Matrix's SAS verification, so users may already know them.
+const SAS_EMOJI: [&str; 64] = [
+    "🐶", "🐱", "🦁", "🐎", "🦄", "🐷", "🐘", "🐰",
+    "🐼", "🐓", "🐧", "🐢", "🐟", "🐙", "🦋", "🌷",
+    "🌳", "🌵", "🍄", "🌏", "🌙", "☁️", "🔥", "🍌",
+    "🍎", "🍓", "🌽", "🍕", "🎂", "❤️", "😀", "🤖",
+    "🎩", "👓", "🔧", "🎅", "👍", "☂️", "⌛", "⏰",
+    "🎁", "💡", "📕", "✏️", "📎", "✂️", "🔒", "🔑",
+    "🔨", "☎️", "🏁", "🚂", "🚲", "✈️", "🚀", "🏆",
+    "⚽", "🎸", "🎺", "🔔", "⚓", "🎧", "📁", "📌",
+];
+
+/// Number of words in the short authentication string (42 bits).
+const SAS_LENGTH: usize = 7;
+
+/// The verifier of an established wormhole. Both sides derive the same value
+/// only if they agreed on the same key, so comparing it out-of-band rules out
+/// a man in the middle.
+#[wasm_bindgen]
+pub struct Verifier {
+    bytes: Vec<u8>,
+}
+
+impl Verifier {
+    pub(crate) fn new(wormhole: &Wormhole) -> Self {
+        Verifier {
+            bytes: wormhole.verifier().as_slice().to_vec(),
+        }
+    }
+
+    fn sas_indices(&self) -> impl Iterator<Item = usize> {
+        let bits = self.bytes[..6]
+            .iter()
+            .fold(0u64, |acc, byte| (acc << 8) | *byte as u64);
+        (0..SAS_LENGTH).map(move |i| ((bits >> (42 - 6 * i)) & 0x3f) as usize)
+    }
+}
+
+#[wasm_bindgen]
+impl Verifier {
+    #[wasm_bindgen(getter)]
+    pub fn bytes(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hex(&self) -> String {
+        self.bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// The verifier as a sequence of words, separated by spaces.
+    #[wasm_bindgen(getter)]
+    pub fn sas(&self) -> String {
+        self.sas_indices()
+            .map(|index| SAS_WORDS[index])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The same string as emoji, separated by spaces, for users to compare
+    /// at a glance. Show it next to `sas`, which names them.
+    #[wasm_bindgen(getter)]
+    pub fn emoji(&self) -> String {
+        self.sas_indices()
+            .map(|index| SAS_EMOJI[index])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Hands the verifier of `wormhole` to `callback`. If the callback returns a
+/// promise, the transfer waits for it. Returning (or resolving to) `false`
+/// rejects the session, everything else lets it proceed.
+pub(crate) async fn confirm(wormhole: &Wormhole, callback: Option<&js_sys::Function>) -> Result<bool, JsValue> {
+    let callback = match callback {
+        Some(callback) => callback,
+        None => return Ok(true),
+    };
+
+    let mut result = callback.call1(&JsValue::NULL, &Verifier::new(wormhole).into())?;
+    if let Some(promise) = result.dyn_ref::<js_sys::Promise>() {
+        result = wasm_bindgen_futures::JsFuture::from(promise.clone()).await?;
+    }
+
+    Ok(result != JsValue::FALSE)
+}
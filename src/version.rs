@@ -0,0 +1,35 @@
+//! What this build supports, see `version()`.
+
+use magic_wormhole::transfer;
+use serde::Serialize;
+
+use crate::compression;
+
+#[derive(Serialize)]
+pub(crate) struct VersionInfo {
+    #[serde(rename = "crate")]
+    crate_version: &'static str,
+    magic_wormhole: &'static str,
+    /// The transit abilities this build can offer.
+    transit_abilities: [&'static str; 2],
+    /// Whether this build was compiled for WebAssembly SIMD, see
+    /// `www/simd.js`.
+    simd: bool,
+    /// The app versions announced with everything enabled. What a config
+    /// actually announces depends on its settings.
+    app_versions: serde_json::Value,
+}
+
+impl VersionInfo {
+    pub(crate) fn current() -> Self {
+        let mut app_versions = serde_json::to_value(&transfer::APP_CONFIG.app_version).unwrap();
+        compression::announce(&mut app_versions);
+        VersionInfo {
+            crate_version: env!("CARGO_PKG_VERSION"),
+            magic_wormhole: env!("MAGIC_WORMHOLE_VERSION"),
+            transit_abilities: ["direct-tcp-v1", "relay-v1"],
+            simd: cfg!(target_feature = "simd128"),
+            app_versions,
+        }
+    }
+}
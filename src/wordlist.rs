@@ -0,0 +1,353 @@
+//! The word lists that wormhole codes are made of.
+//!
+//! The default is the PGP word list: every byte has an "even"
+//! (two-syllable) and an "odd" (three-syllable) word. Codes alternate
+//! between the two lists, starting with an odd word, so a word's position
+//! tells which list it has to come from.
+
+/// `PGP_WORDS[byte] = [even, odd]`.
+const PGP_WORDS: [[&str; 2]; 256] = [
+    ["aardvark", "adroitness"],
+    ["absurd", "adviser"],
+    ["accrue", "aftermath"],
+    ["acme", "aggregate"],
+    ["adrift", "alkali"],
+    ["adult", "almighty"],
+    ["afflict", "amulet"],
+    ["ahead", "amusement"],
+    ["aimless", "antenna"],
+    ["algol", "applicant"],
+    ["allow", "apollo"],
+    ["alone", "armistice"],
+    ["ammo", "article"],
+    ["ancient", "asteroid"],
+    ["apple", "atlantic"],
+    ["artist", "atmosphere"],
+    ["assume", "autopsy"],
+    ["athens", "babylon"],
+    ["atlas", "backwater"],
+    ["aztec", "barbecue"],
+    ["baboon", "belowground"],
+    ["backfield", "bifocals"],
+    ["backward", "bodyguard"],
+    ["banjo", "bookseller"],
+    ["beaming", "borderline"],
+    ["bedlamp", "bottomless"],
+    ["beehive", "bradbury"],
+    ["beeswax", "bravado"],
+    ["befriend", "brazilian"],
+    ["belfast", "breakaway"],
+    ["berserk", "burlington"],
+    ["billiard", "businessman"],
+    ["bison", "butterfat"],
+    ["blackjack", "camelot"],
+    ["blockade", "candidate"],
+    ["blowtorch", "cannonball"],
+    ["bluebird", "capricorn"],
+    ["bombast", "caravan"],
+    ["bookshelf", "caretaker"],
+    ["brackish", "celebrate"],
+    ["breadline", "cellulose"],
+    ["breakup", "certify"],
+    ["brickyard", "chambermaid"],
+    ["briefcase", "cherokee"],
+    ["burbank", "chicago"],
+    ["button", "clergyman"],
+    ["buzzard", "coherence"],
+    ["cement", "combustion"],
+    ["chairlift", "commando"],
+    ["chatter", "company"],
+    ["checkup", "component"],
+    ["chisel", "concurrent"],
+    ["choking", "confidence"],
+    ["chopper", "conformist"],
+    ["christmas", "congregate"],
+    ["clamshell", "consensus"],
+    ["classic", "consulting"],
+    ["classroom", "corporate"],
+    ["cleanup", "corrosion"],
+    ["clockwork", "councilman"],
+    ["cobra", "crossover"],
+    ["commence", "crucifix"],
+    ["concert", "cumbersome"],
+    ["cowbell", "customer"],
+    ["crackdown", "dakota"],
+    ["cranky", "decadence"],
+    ["crowfoot", "december"],
+    ["crucial", "decimal"],
+    ["crumpled", "designing"],
+    ["crusade", "detector"],
+    ["cubic", "detergent"],
+    ["dashboard", "determine"],
+    ["deadbolt", "dictator"],
+    ["deckhand", "dinosaur"],
+    ["dogsled", "direction"],
+    ["dragnet", "disable"],
+    ["drainage", "disbelief"],
+    ["dreadful", "disruptive"],
+    ["drifter", "distortion"],
+    ["dropper", "document"],
+    ["drumbeat", "embezzle"],
+    ["drunken", "enchanting"],
+    ["dupont", "enrollment"],
+    ["dwelling", "enterprise"],
+    ["eating", "equation"],
+    ["edict", "equipment"],
+    ["egghead", "escapade"],
+    ["eightball", "eskimo"],
+    ["endorse", "everyday"],
+    ["endow", "examine"],
+    ["enlist", "existence"],
+    ["erase", "exodus"],
+    ["escape", "fascinate"],
+    ["exceed", "filament"],
+    ["eyeglass", "finicky"],
+    ["eyetooth", "forever"],
+    ["facial", "fortitude"],
+    ["fallout", "frequency"],
+    ["flagpole", "gadgetry"],
+    ["flatfoot", "galveston"],
+    ["flytrap", "getaway"],
+    ["fracture", "glossary"],
+    ["framework", "gossamer"],
+    ["freedom", "graduate"],
+    ["frighten", "gravity"],
+    ["gazelle", "guitarist"],
+    ["geiger", "hamburger"],
+    ["glitter", "hamilton"],
+    ["glucose", "handiwork"],
+    ["goggles", "hazardous"],
+    ["goldfish", "headwaters"],
+    ["gremlin", "hemisphere"],
+    ["guidance", "hesitate"],
+    ["hamlet", "hideaway"],
+    ["highchair", "holiness"],
+    ["hockey", "hurricane"],
+    ["indoors", "hydraulic"],
+    ["indulge", "impartial"],
+    ["inverse", "impetus"],
+    ["involve", "inception"],
+    ["island", "indigo"],
+    ["jawbone", "inertia"],
+    ["keyboard", "infancy"],
+    ["kickoff", "inferno"],
+    ["kiwi", "informant"],
+    ["klaxon", "insincere"],
+    ["locale", "insurgent"],
+    ["lockup", "integrate"],
+    ["merit", "intention"],
+    ["minnow", "inventive"],
+    ["miser", "istanbul"],
+    ["mohawk", "jamaica"],
+    ["mural", "jupiter"],
+    ["music", "leprosy"],
+    ["necklace", "letterhead"],
+    ["neptune", "liberty"],
+    ["newborn", "maritime"],
+    ["nightbird", "matchmaker"],
+    ["oakland", "maverick"],
+    ["obtuse", "medusa"],
+    ["offload", "megaton"],
+    ["optic", "microscope"],
+    ["orca", "microwave"],
+    ["payday", "midsummer"],
+    ["peachy", "millionaire"],
+    ["pheasant", "miracle"],
+    ["physique", "misnomer"],
+    ["playhouse", "molasses"],
+    ["pluto", "molecule"],
+    ["preclude", "montana"],
+    ["prefer", "monument"],
+    ["preshrunk", "mosquito"],
+    ["printer", "narrative"],
+    ["prowler", "nebula"],
+    ["pupil", "newsletter"],
+    ["puppy", "norwegian"],
+    ["python", "october"],
+    ["quadrant", "ohio"],
+    ["quiver", "onlooker"],
+    ["quota", "opulent"],
+    ["ragtime", "orlando"],
+    ["ratchet", "outfielder"],
+    ["rebirth", "pacific"],
+    ["reform", "pandemic"],
+    ["regain", "pandora"],
+    ["reindeer", "paperweight"],
+    ["rematch", "paragon"],
+    ["repay", "paragraph"],
+    ["retouch", "paramount"],
+    ["revenge", "passenger"],
+    ["reward", "pedigree"],
+    ["rhythm", "pegasus"],
+    ["ribcage", "penetrate"],
+    ["ringbolt", "perceptive"],
+    ["robust", "performance"],
+    ["rocker", "pharmacy"],
+    ["ruffled", "phonetic"],
+    ["sailboat", "photograph"],
+    ["sawdust", "pioneer"],
+    ["scallion", "pocketful"],
+    ["scenic", "politeness"],
+    ["scorecard", "positive"],
+    ["scotland", "potato"],
+    ["seabird", "processor"],
+    ["select", "provincial"],
+    ["sentence", "proximate"],
+    ["shadow", "puberty"],
+    ["shamrock", "publisher"],
+    ["showgirl", "pyramid"],
+    ["skullcap", "quantity"],
+    ["skydive", "racketeer"],
+    ["slingshot", "rebellion"],
+    ["slowdown", "recipe"],
+    ["snapline", "recover"],
+    ["snapshot", "repellent"],
+    ["snowcap", "replica"],
+    ["snowslide", "reproduce"],
+    ["solo", "resistor"],
+    ["southward", "responsive"],
+    ["soybean", "retraction"],
+    ["spaniel", "retrieval"],
+    ["spearhead", "retrospect"],
+    ["spellbind", "revenue"],
+    ["spheroid", "revival"],
+    ["spigot", "revolver"],
+    ["spindle", "sandalwood"],
+    ["spyglass", "sardonic"],
+    ["stagehand", "saturday"],
+    ["stagnate", "savagery"],
+    ["stairway", "scavenger"],
+    ["standard", "sensation"],
+    ["stapler", "sociable"],
+    ["steamship", "souvenir"],
+    ["sterling", "specialist"],
+    ["stockman", "speculate"],
+    ["stopwatch", "stethoscope"],
+    ["stormy", "stupendous"],
+    ["sugar", "supportive"],
+    ["surmount", "surrender"],
+    ["suspense", "suspicious"],
+    ["sweatband", "sympathy"],
+    ["swelter", "tambourine"],
+    ["tactics", "telephone"],
+    ["talon", "therapist"],
+    ["tapeworm", "tobacco"],
+    ["tempest", "tolerance"],
+    ["tiger", "tomorrow"],
+    ["tissue", "torpedo"],
+    ["tonic", "tradition"],
+    ["topmost", "travesty"],
+    ["tracker", "trombonist"],
+    ["transit", "truncated"],
+    ["trauma", "typewriter"],
+    ["treadmill", "ultimate"],
+    ["trojan", "undaunted"],
+    ["trouble", "underfoot"],
+    ["tumor", "unicorn"],
+    ["tunnel", "unify"],
+    ["tycoon", "universe"],
+    ["uncut", "unravel"],
+    ["unearth", "upcoming"],
+    ["unwind", "vacancy"],
+    ["uproot", "vagabond"],
+    ["upset", "vertigo"],
+    ["upshot", "virginia"],
+    ["vapor", "visitor"],
+    ["village", "vocalist"],
+    ["virus", "voyager"],
+    ["vulcan", "warranty"],
+    ["waffle", "waterloo"],
+    ["wallet", "whimsical"],
+    ["watchword", "wichita"],
+    ["wayside", "wilmington"],
+    ["willow", "wyoming"],
+    ["woodlark", "yesteryear"],
+    ["zulu", "yucatan"],
+];
+
+/// The PGP word for `byte` at `position` (0 for the first word after the
+/// nameplate), from the odd list first like the default `Wordlist`.
+pub(crate) fn pgp_word(byte: u8, position: usize) -> &'static str {
+    PGP_WORDS[byte as usize][1 - position % 2]
+}
+
+/// One or more lists of words, used in turn for the words of a code.
+#[derive(Clone, Debug)]
+pub(crate) struct Wordlist {
+    lists: Vec<Vec<String>>,
+}
+
+impl Default for Wordlist {
+    fn default() -> Self {
+        let column = |column: usize| PGP_WORDS.iter().map(|words| words[column].to_owned()).collect();
+        Wordlist {
+            lists: vec![column(1), column(0)],
+        }
+    }
+}
+
+impl Wordlist {
+    /// Words are matched case-insensitively and must not contain a `-` or
+    /// whitespace.
+    pub(crate) fn new(lists: Vec<Vec<String>>) -> Result<Self, String> {
+        if lists.is_empty() || lists.iter().any(Vec::is_empty) {
+            return Err("A word list must not be empty".to_owned());
+        }
+        let lists = lists
+            .into_iter()
+            .map(|list| {
+                list.into_iter()
+                    .map(|word| {
+                        if word.is_empty() || word.chars().any(|c| c == '-' || c.is_whitespace()) {
+                            Err(format!("Invalid word in word list: {:?}", word))
+                        } else {
+                            Ok(word.to_lowercase())
+                        }
+                    })
+                    .collect::<Result<_, _>>()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Wordlist { lists })
+    }
+
+    /// The words that may appear at `position` (0 for the first word after
+    /// the nameplate).
+    fn words_at(&self, position: usize) -> &[String] {
+        &self.lists[position % self.lists.len()]
+    }
+
+    /// Completes the last word of `code`, a code typed so far such as
+    /// `7-gui`. Returns the candidate codes, each followed by a `-` unless it
+    /// completes the last of `num_words` words. Nothing is completed before
+    /// the nameplate has been ended with a `-`.
+    pub(crate) fn complete(&self, code: &str, num_words: usize) -> Vec<String> {
+        let code = code.to_lowercase();
+        let (nameplate, words) = match code.split_once('-') {
+            Some(parts) => parts,
+            None => return Vec::new(),
+        };
+        let words: Vec<&str> = words.split('-').collect();
+        let position = words.len() - 1;
+        if position >= num_words {
+            return Vec::new();
+        }
+        let partial = words[position];
+        let completed = &words[..position];
+
+        self.words_at(position)
+            .iter()
+            .filter(|word| word.starts_with(partial))
+            .map(|word| {
+                let mut candidate = std::iter::once(nameplate)
+                    .chain(completed.iter().copied())
+                    .chain(std::iter::once(word.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("-");
+                if position + 1 < num_words {
+                    candidate.push('-');
+                }
+                candidate
+            })
+            .collect()
+    }
+}
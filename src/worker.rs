@@ -0,0 +1,248 @@
+//! Message protocol for running transfers in a dedicated Worker, away from
+//! the UI thread.
+//!
+//! The worker passes every message it gets from the page to
+//! `ClientConfig.handle_message`. Requests are objects with a `type`:
+//!
+//! - `{type: "send", file: File}`
+//! - `{type: "send-bytes", name: string, data: ArrayBuffer}`
+//! - `{type: "receive", code: string, sha256?: string}`, failing unless the
+//!   received data has the given SHA-256
+//! - `{type: "receive-opfs", code: string, name?: string, sha256?: string}`,
+//!   writing the file to the Origin Private File System as it arrives, see
+//!   `ClientConfig.receive_to_opfs`
+//!
+//! While a request runs, the worker posts these events back:
+//!
+//! - `{type: "status", message: string}`
+//! - `{type: "phase", phase: string}`, see `WormholeSession` for the phases
+//! - `{type: "welcome", message: string}`
+//! - `{type: "code", code: string, nameplate: string, words: string[]}`
+//! - `{type: "alternate-code", code: string, nameplate: string, words: string[]}`,
+//!   see `ClientConfig.set_alternate_code_length`
+//! - `{type: "countdown", remaining_ms: number}` and `{type: "expired"}`,
+//!   see `ClientConfig.set_code_expiry`
+//! - `{type: "connected", app_versions: object, sas: string, emoji: string}`,
+//!   with the verifier as words and as emoji, see `Verifier`
+//! - `{type: "transit", kind: "direct" | "relay" | "unknown", relay_url?: string}`
+//! - `{type: "progress", current: number, total: number, bytes_per_second: number, smoothed_bytes_per_second: number, eta_ms?: number, entry?: EntryProgress}`,
+//!   see the `progress` event of `WormholeSession`
+//! - `{type: "stalled", idle_ms: number}`, see `ClientConfig.set_stall_warning`
+//! - `{type: "offline"}` and `{type: "resumed"}`, while the browser has no
+//!   network connection and the transfer is held
+//! - `{type: "wrong-code"}`, when key confirmation with the peer failed
+//! - `{type: "timeout", stage: string, message: string}`, see
+//!   `ClientConfig.set_timeouts`
+//! - `{type: "rejected", message: string}`, when the peer aborted the transfer
+//! - `{type: "error", message: string}`
+//! - `{type: "sent", stats: object}`
+//! - `{type: "received", filename: string, raw_filename: string, filesize: number, mime_type: string, last_modified?: number, description?: string, sha256: string, data: ArrayBuffer, stats: object}`,
+//!   with `data` transferred rather than copied. `filename` is sanitized,
+//!   `raw_filename` is what the sender offered.
+//! - `{type: "saved", filename: string, raw_filename: string, filesize: number, mime_type: string, last_modified?: number, description?: string, sha256: string, stats: object}`,
+//!   once a `receive-opfs` request is done. `filename` is the name in the
+//!   file system.
+//!
+//! `stats` has the fields of `TransferStats`.
+
+use js_sys::{Array, Object, Reflect};
+use magic_wormhole::{Code, Wormhole};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::events::Events;
+use crate::js;
+use crate::phase::Phase;
+use crate::stats::Progress;
+use crate::timeout::Timeout;
+use crate::{ReceiveResult, SavedFile, TransferStats, TransitInfo, Verifier, WormholeCode};
+
+/// Posts the events of a transfer to the page that owns the worker.
+pub(crate) struct WorkerEvents {
+    scope: web_sys::DedicatedWorkerGlobalScope,
+}
+
+impl WorkerEvents {
+    pub(crate) fn new() -> Self {
+        WorkerEvents {
+            scope: js_sys::global().unchecked_into(),
+        }
+    }
+
+    fn post(&self, kind: &str, fields: &[(&str, JsValue)]) {
+        let message = message(kind, fields);
+        if let Err(e) = self.scope.post_message(&message) {
+            console_log!("Error posting {} message: {:?}", kind, e);
+        }
+    }
+
+    pub(crate) fn sent(&self, stats: TransferStats) {
+        self.post("sent", &[("stats", js::to_js_or_undefined(&stats))]);
+    }
+
+    pub(crate) fn received(&self, result: ReceiveResult) {
+        let data = js_sys::Uint8Array::from(&result.data[..]).buffer();
+        let message = message("received", &[
+            ("filename", JsValue::from_str(&result.filename)),
+            ("raw_filename", JsValue::from_str(&result.raw_filename)),
+            ("filesize", JsValue::from(result.filesize as f64)),
+            ("mime_type", JsValue::from_str(&result.mime_type())),
+            ("last_modified", JsValue::from(result.last_modified())),
+            ("description", JsValue::from(result.description())),
+            ("sha256", JsValue::from_str(&result.sha256)),
+            ("data", data.clone().into()),
+            ("stats", js::to_js_or_undefined(&result.stats)),
+        ]);
+        if let Err(e) = self.scope.post_message_with_transfer(&message, &Array::of1(&data)) {
+            console_log!("Error posting received message: {:?}", e);
+        }
+    }
+
+    pub(crate) fn saved(&self, saved: SavedFile) {
+        self.post("saved", &[
+            ("filename", JsValue::from_str(&saved.filename())),
+            ("raw_filename", JsValue::from_str(&saved.raw_filename)),
+            ("filesize", JsValue::from(saved.filesize as f64)),
+            ("mime_type", JsValue::from_str(&saved.mime_type())),
+            ("last_modified", JsValue::from(saved.last_modified())),
+            ("description", JsValue::from(saved.description())),
+            ("sha256", JsValue::from_str(&saved.sha256)),
+            ("stats", js::to_js_or_undefined(&saved.stats)),
+        ]);
+    }
+}
+
+impl Events for WorkerEvents {
+    fn status(&self, message: &str) {
+        self.post("status", &[("message", JsValue::from_str(message))]);
+    }
+
+    fn phase(&self, phase: Phase) {
+        self.post("phase", &[("phase", JsValue::from_str(phase.as_str()))]);
+    }
+
+    fn welcome(&self, message: &str) {
+        self.post("welcome", &[("message", JsValue::from_str(message))]);
+    }
+
+    fn code(&self, code: &Code) {
+        let parts = WormholeCode::from(code);
+        self.post("code", &[
+            ("code", JsValue::from_str(code.as_ref())),
+            ("nameplate", JsValue::from_str(&parts.nameplate())),
+            ("words", parts.words().into()),
+        ]);
+    }
+
+    fn alternate_code(&self, code: &Code) {
+        let parts = WormholeCode::from(code);
+        self.post("alternate-code", &[
+            ("code", JsValue::from_str(code.as_ref())),
+            ("nameplate", JsValue::from_str(&parts.nameplate())),
+            ("words", parts.words().into()),
+        ]);
+    }
+
+    fn code_countdown(&self, remaining_ms: f64) {
+        self.post("countdown", &[("remaining_ms", JsValue::from(remaining_ms))]);
+    }
+
+    fn code_expired(&self) {
+        self.post("expired", &[]);
+    }
+
+    fn connected(&self, wormhole: &Wormhole) {
+        let verifier = Verifier::new(wormhole);
+        self.post("connected", &[
+            ("app_versions", js::to_js_or_undefined(&wormhole.peer_version())),
+            ("sas", JsValue::from_str(&verifier.sas())),
+            ("emoji", JsValue::from_str(&verifier.emoji())),
+        ]);
+    }
+
+    fn transit(&self, info: TransitInfo) {
+        self.post("transit", &[
+            ("kind", JsValue::from_str(&info.kind())),
+            ("relay_url", JsValue::from(info.relay_url())),
+        ]);
+    }
+
+    fn progress(&self, progress: Progress) {
+        self.post("progress", &[
+            ("current", JsValue::from(progress.current as f64)),
+            ("total", JsValue::from(progress.total as f64)),
+            ("bytes_per_second", JsValue::from(progress.bytes_per_second)),
+            ("smoothed_bytes_per_second", JsValue::from(progress.smoothed_bytes_per_second)),
+            ("eta_ms", JsValue::from(progress.eta_ms)),
+            ("entry", progress.entry.as_ref().map_or(JsValue::UNDEFINED, js::to_js_or_undefined)),
+        ]);
+    }
+
+    fn stalled(&self, idle_ms: f64) {
+        self.post("stalled", &[("idle_ms", JsValue::from(idle_ms))]);
+    }
+
+    fn network(&self, online: bool) {
+        self.post(if online { "resumed" } else { "offline" }, &[]);
+    }
+
+    fn wrong_code(&self) {
+        self.post("wrong-code", &[]);
+    }
+
+    fn timed_out(&self, timeout: Timeout) {
+        self.post("timeout", &[
+            ("stage", JsValue::from_str(timeout.as_str())),
+            ("message", JsValue::from_str(timeout.message())),
+        ]);
+    }
+
+    fn rejected(&self, message: &str) {
+        self.post("rejected", &[("message", JsValue::from_str(message))]);
+    }
+
+    fn error(&self, message: &str) {
+        self.post("error", &[("message", JsValue::from_str(message))]);
+    }
+}
+
+fn message(kind: &str, fields: &[(&str, JsValue)]) -> Object {
+    let message = Object::new();
+    let _ = Reflect::set(&message, &JsValue::from_str("type"), &JsValue::from_str(kind));
+    for (name, value) in fields {
+        let _ = Reflect::set(&message, &JsValue::from_str(name), value);
+    }
+    message
+}
+
+/// A request posted to the worker, see the module documentation.
+pub(crate) enum Request {
+    Send(web_sys::File),
+    SendBytes(String, js_sys::Uint8Array),
+    Receive(String, Option<String>),
+    ReceiveToOpfs(String, Option<String>, Option<String>),
+}
+
+impl Request {
+    pub(crate) fn parse(message: &JsValue) -> Result<Self, String> {
+        let field = |name: &str| Reflect::get(message, &JsValue::from_str(name)).unwrap_or(JsValue::UNDEFINED);
+        let string = |name: &str| field(name).as_string().ok_or_else(|| format!("Missing string field {:?}", name));
+
+        match string("type")?.as_str() {
+            "send" => field("file")
+                .dyn_into()
+                .map(Request::Send)
+                .map_err(|_| "Missing File field \"file\"".to_owned()),
+            "send-bytes" => {
+                let data = field("data");
+                if !data.is_instance_of::<js_sys::ArrayBuffer>() {
+                    return Err("Missing ArrayBuffer field \"data\"".to_owned());
+                }
+                Ok(Request::SendBytes(string("name")?, js_sys::Uint8Array::new(&data)))
+            }
+            "receive" => Ok(Request::Receive(string("code")?, field("sha256").as_string())),
+            "receive-opfs" => Ok(Request::ReceiveToOpfs(string("code")?, field("name").as_string(), field("sha256").as_string())),
+            other => Err(format!("Unknown request type {:?}", other)),
+        }
+    }
+}
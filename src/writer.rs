@@ -0,0 +1,69 @@
+//! `AsyncWrite`s over the JS file handles received files can be written
+//! to, besides the `WritableStream`s of `stream::StreamWriter`.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::AsyncWrite;
+use wasm_bindgen::prelude::*;
+
+use crate::js::js_error;
+
+#[wasm_bindgen]
+extern {
+    /// A file opened with `createSyncAccessHandle()`, only available to
+    /// dedicated workers.
+    pub(crate) type FileSystemSyncAccessHandle;
+
+    #[wasm_bindgen(method, catch)]
+    fn write(this: &FileSystemSyncAccessHandle, data: &[u8], options: &JsValue) -> Result<f64, JsValue>;
+
+    #[wasm_bindgen(method, catch)]
+    fn truncate(this: &FileSystemSyncAccessHandle, size: f64) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch)]
+    fn flush(this: &FileSystemSyncAccessHandle) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch)]
+    fn close(this: &FileSystemSyncAccessHandle) -> Result<(), JsValue>;
+}
+
+/// Writes to a sync access handle from its start, replacing what was there.
+/// The handle is closed when it is dropped, keeping what was written.
+pub(crate) struct SyncAccessWriter {
+    handle: FileSystemSyncAccessHandle,
+    at: u64,
+}
+
+impl SyncAccessWriter {
+    pub(crate) fn new(handle: FileSystemSyncAccessHandle) -> Result<Self, JsValue> {
+        handle.truncate(0.0)?;
+        Ok(SyncAccessWriter { handle, at: 0 })
+    }
+}
+
+impl AsyncWrite for SyncAccessWriter {
+    fn poll_write(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let options = js_sys::Object::new();
+        js_sys::Reflect::set(&options, &JsValue::from_str("at"), &JsValue::from(self.at as f64)).map_err(js_error)?;
+        let written = self.handle.write(buf, &options).map_err(js_error)? as usize;
+        self.at += written as u64;
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.handle.flush().map_err(js_error))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.handle.flush().and_then(|_| self.handle.close()).map_err(js_error))
+    }
+}
+
+impl Drop for SyncAccessWriter {
+    fn drop(&mut self) {
+        // Closing a closed handle does nothing
+        let _ = self.handle.close();
+    }
+}
@@ -0,0 +1,97 @@
+//! Giving the browser a turn during long stretches of a transfer, see
+//! `ClientConfig.set_yield_budget`.
+//!
+//! While data is at hand, reading, encrypting and writing it never has to
+//! wait, and the transfer task keeps the thread until the connection or the
+//! file makes it wait. On the main thread that freezes the page.
+//!
+//! A hidden page has nothing to render and no input to handle, so no turns
+//! are given while `document.hidden` is set: a transfer in a background tab
+//! runs as fast as it would in a worker.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::{AsyncRead, AsyncWrite};
+use wasm_bindgen::prelude::*;
+
+use crate::timer;
+
+/// The default budget: the point at which browsers count a task as long.
+pub(crate) const DEFAULT_YIELD_BUDGET_MS: f64 = 50.0;
+
+/// Makes the transfer wait for the next task once `budget_ms` passed since
+/// it last did, so that the browser can handle input and render in between.
+/// A task rather than a microtask, because the browser only gets to run its
+/// own work between tasks, and a message rather than a `setTimeout`,
+/// because hidden tabs run timeouts at most once a second: a transfer in a
+/// background tab would otherwise slow to one budget's worth of data per
+/// second.
+pub(crate) struct Yielding<T> {
+    inner: T,
+    budget_ms: Option<f64>,
+    since: f64,
+    pause: Option<timer::NextTask>,
+}
+
+impl<T> Yielding<T> {
+    /// Without `budget_ms`, everything passes through unchanged.
+    pub(crate) fn new(inner: T, budget_ms: Option<f64>) -> Self {
+        Yielding {
+            inner,
+            budget_ms,
+            since: js_sys::Date::now(),
+            pause: None,
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn poll_turn(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let budget_ms = match self.budget_ms {
+            Some(budget_ms) => budget_ms,
+            None => return Poll::Ready(()),
+        };
+        if self.pause.is_none() && (js_sys::Date::now() - self.since < budget_ms || page_hidden()) {
+            return Poll::Ready(());
+        }
+        let pause = self.pause.get_or_insert_with(timer::next_task);
+        futures::ready!(Pin::new(pause).poll(cx));
+        self.pause = None;
+        self.since = js_sys::Date::now();
+        Poll::Ready(())
+    }
+}
+
+/// Whether this runs in a page that is not visible, per the Page Visibility
+/// API. Workers and Node.js have no `document` and count as visible.
+fn page_hidden() -> bool {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("document"))
+        .and_then(|document| js_sys::Reflect::get(&document, &JsValue::from_str("hidden")))
+        .is_ok_and(|hidden| hidden.as_bool() == Some(true))
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Yielding<T> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+        futures::ready!(self.poll_turn(cx));
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Yielding<T> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        futures::ready!(self.poll_turn(cx));
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
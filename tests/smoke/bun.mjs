@@ -0,0 +1,10 @@
+// Loads the package under Bun:
+//
+//     wasm-pack build --target web
+//     bun tests/smoke/bun.mjs
+
+import init, * as wasm from "../../pkg/magic_wormhole_wasm.js";
+import { check } from "./checks.mjs";
+
+await init(await Bun.file(new URL("../../pkg/magic_wormhole_wasm_bg.wasm", import.meta.url)).arrayBuffer());
+check(wasm, "bun");
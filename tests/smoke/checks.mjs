@@ -0,0 +1,27 @@
+// Checks shared by the runtime smoke tests. They only load the module and
+// use the parts that need no network, so they run offline.
+
+export function check(wasm, expectedRuntime) {
+    const runtime = wasm.runtime();
+    if (runtime !== expectedRuntime) {
+        throw new Error(`runtime() is ${runtime}, expected ${expectedRuntime}`);
+    }
+    if (typeof WebSocket !== "function") {
+        throw new Error("No WebSocket global for the rendezvous connection");
+    }
+
+    const config = wasm.ClientConfig.client_init(
+        "lothar.com/wormhole/text-or-file-xfer",
+        "ws://localhost:4000/v1",
+        "tcp://localhost:4001",
+        2,
+    );
+    const session = new wasm.WormholeSession(config);
+    session.close();
+
+    const code = wasm.WormholeCode.parse("4-hurricane-equipment");
+    if (code.nameplate !== "4" || code.toString() !== "4-hurricane-equipment") {
+        throw new Error(`Code parsed as ${code.nameplate} / ${code}`);
+    }
+    console.log(`${runtime}: ok`);
+}
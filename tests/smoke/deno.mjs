@@ -0,0 +1,10 @@
+// Loads the package under Deno:
+//
+//     wasm-pack build --target web
+//     deno run --allow-read tests/smoke/deno.mjs
+
+import init, * as wasm from "../../pkg/magic_wormhole_wasm.js";
+import { check } from "./checks.mjs";
+
+await init(await Deno.readFile(new URL("../../pkg/magic_wormhole_wasm_bg.wasm", import.meta.url)));
+check(wasm, "deno");
@@ -7,7 +7,557 @@ use wasm_bindgen_test::*;
 
 wasm_bindgen_test_configure!(run_in_browser);
 
+/// A client for the local test servers, for tests that never connect.
+fn test_config() -> magic_wormhole_wasm::ClientConfig {
+    magic_wormhole_wasm::ClientConfig::client_init(
+        "lothar.com/wormhole/text-or-file-xfer".into(),
+        "ws://localhost:4000/v1".into(),
+        "tcp://localhost:4001".into(),
+        2,
+    )
+    .unwrap()
+}
+
 #[wasm_bindgen_test]
+#[allow(clippy::eq_op)]
 fn pass() {
     assert_eq!(1 + 1, 2);
 }
+
+#[wasm_bindgen_test]
+fn slice_bounds_past_2gb() {
+    let offset = i32::MAX as u64 - 10;
+    assert_eq!(
+        magic_wormhole_wasm::FileWrapper::slice_bounds(offset, 100, offset + 1000),
+        (2147483637.0, 2147483737.0)
+    );
+}
+
+#[wasm_bindgen_test]
+fn slice_bounds_at_end_of_4gb_file() {
+    let size = u32::MAX as u64 + 1;
+    assert_eq!(
+        magic_wormhole_wasm::FileWrapper::slice_bounds(size - 10, 100, size),
+        (4294967286.0, 4294967296.0)
+    );
+    assert_eq!(
+        magic_wormhole_wasm::FileWrapper::slice_bounds(size, 100, size),
+        (4294967296.0, 4294967296.0)
+    );
+}
+
+#[wasm_bindgen_test]
+fn client_init_rejects_invalid_appid() {
+    let init = |appid: &str| {
+        magic_wormhole_wasm::ClientConfig::client_init(
+            appid.into(),
+            "ws://localhost:4000/v1".into(),
+            "tcp://localhost:4001".into(),
+            2,
+        )
+    };
+    assert!(init("  lothar.com/wormhole/text-or-file-xfer ").is_ok());
+    assert!(init("   ").is_err());
+    assert!(init("my app").is_err());
+}
+
+#[wasm_bindgen_test]
+fn wormhole_transfer_uri_round_trip() {
+    use magic_wormhole_wasm::WormholeTransferUri;
+
+    let uri = WormholeTransferUri::new("4-hurricane-equipment".into(), Some("ws://relay.example:4000/v1".into()));
+    let rendered = uri.to_uri_string();
+    assert_eq!(
+        rendered,
+        "wormhole-transfer:4-hurricane-equipment?rendezvous=ws%3A%2F%2Frelay.example%3A4000%2Fv1"
+    );
+    assert_eq!(WormholeTransferUri::parse(&rendered).unwrap(), uri);
+
+    let parsed = WormholeTransferUri::parse("wormhole-transfer:7-guitarist-revenge?version=0&role=leader").unwrap();
+    assert_eq!(parsed.code(), "7-guitarist-revenge");
+    assert_eq!(parsed.rendezvous_url(), None);
+    assert!(parsed.leader());
+
+    assert!(WormholeTransferUri::parse("wormhole-transfer:7-guitarist-revenge?version=1").is_err());
+    assert!(WormholeTransferUri::parse("https://example.com/7-guitarist-revenge").is_err());
+}
+
+#[wasm_bindgen_test]
+fn wormhole_code_parts() {
+    use magic_wormhole_wasm::WormholeCode;
+
+    let code = WormholeCode::parse(" 4-hurricane-equipment ").unwrap();
+    assert_eq!(code.nameplate(), "4");
+    assert_eq!(code.words().length(), 2);
+    assert_eq!(code.to_code_string(), "4-hurricane-equipment");
+
+    assert!(WormholeCode::parse("hurricane-equipment").is_err());
+    assert!(WormholeCode::parse("4-").is_err());
+}
+
+#[wasm_bindgen_test]
+fn complete_code_respects_word_parity() {
+    let config = test_config();
+    let complete = |code: &str| -> Vec<String> {
+        config.complete_code(code).iter().map(|word| word.as_string().unwrap()).collect()
+    };
+
+    assert_eq!(complete("7-guita"), vec!["7-guitarist-"]);
+    assert_eq!(complete("7-Guitarist-reve"), vec!["7-guitarist-revenge"]);
+    // "revenge" is an even word and can't start a code
+    assert!(complete("7-reveng").is_empty());
+    assert!(complete("7").is_empty());
+}
+
+#[wasm_bindgen_test]
+fn complete_code_with_custom_wordlist() {
+    let mut config = test_config();
+    let words: js_sys::Array = ["Apfel", "Ampel", "Birne"].iter().map(|&word| wasm_bindgen::JsValue::from_str(word)).collect();
+    config.set_wordlist(js_sys::Array::of1(&words)).unwrap();
+
+    let completions: Vec<String> = config.complete_code("3-a").iter().map(|word| word.as_string().unwrap()).collect();
+    assert_eq!(completions, vec!["3-apfel-", "3-ampel-"]);
+
+    assert!(config.set_wordlist(js_sys::Array::new()).is_err());
+}
+
+#[wasm_bindgen_test]
+fn sanitize_filename_strips_paths_and_control_characters() {
+    use magic_wormhole_wasm::sanitize_filename;
+
+    assert_eq!(sanitize_filename("report.pdf"), "report.pdf");
+    assert_eq!(sanitize_filename("../../etc/passwd"), "passwd");
+    assert_eq!(sanitize_filename("C:\\Users\\me\\notes.txt"), "notes.txt");
+    assert_eq!(sanitize_filename("bad\u{0}name?.txt"), "bad_name_.txt");
+    assert_eq!(sanitize_filename(".."), "file");
+    assert_eq!(sanitize_filename("dir/"), "file");
+
+    let long = format!("{}.tar.gz", "a".repeat(300));
+    let sanitized = sanitize_filename(&long);
+    assert_eq!(sanitized.len(), 255);
+    assert!(sanitized.ends_with(".gz"));
+}
+
+#[wasm_bindgen_test]
+fn slice_bounds_near_max_safe_integer() {
+    let size = (1u64 << 53) - 1;
+    assert_eq!(
+        magic_wormhole_wasm::FileWrapper::slice_bounds(size - 10, 100, size),
+        ((size - 10) as f64, size as f64)
+    );
+}
+
+#[wasm_bindgen_test]
+fn size_setters_reject_inexact_numbers() {
+    let mut config = test_config();
+
+    assert!(config.set_max_accept_size(Some(4294967296.0)).is_ok());
+    assert!(config.set_max_accept_size(Some(9007199254740991.0)).is_ok());
+    assert!(config.set_max_accept_size(Some(9007199254740992.0)).is_err());
+    assert!(config.set_max_accept_size(Some(-1.0)).is_err());
+    assert!(config.set_max_accept_size(Some(1.5)).is_err());
+    assert!(config.set_max_accept_size(Some(f64::NAN)).is_err());
+    assert!(config.set_max_accept_size(None).is_ok());
+}
+
+/// `value`, a `BigInt`, as a `u64`.
+fn big(value: &wasm_bindgen::JsValue) -> u64 {
+    use wasm_bindgen::JsCast;
+    let value: &js_sys::BigInt = value.dyn_ref().expect("a BigInt");
+    value.to_string(10).unwrap().as_string().unwrap().parse().unwrap()
+}
+
+#[wasm_bindgen_test]
+fn declared_sizes_past_2_pow_53_stay_exact() {
+    let sizes = [(1u64 << 32) + 1, (1u64 << 53) + 1];
+    for size in sizes {
+        assert_eq!(big(&magic_wormhole_wasm::size::declared(size)), size);
+    }
+
+    let offer = magic_wormhole_wasm::Offer::archive(
+        "huge.zip".into(),
+        u64::MAX,
+        vec![("dir/four_gib".into(), sizes[0]), ("dir/past_safe".into(), sizes[1])],
+    );
+    let entries = js_sys::Array::from(&offer.entries().unwrap());
+    let field = |index: u32, name: &str| js_sys::Reflect::get(&entries.get(index), &name.into()).unwrap();
+    assert_eq!(entries.length(), 3);
+    assert_eq!(field(0, "kind"), "directory");
+    assert_eq!(big(&field(0, "size")), 0);
+    assert_eq!(field(1, "name"), "dir/four_gib");
+    assert_eq!(big(&field(1, "size")), sizes[0]);
+    assert_eq!(field(2, "name"), "dir/past_safe");
+    assert_eq!(big(&field(2, "size")), sizes[1]);
+}
+
+#[wasm_bindgen_test]
+fn init_with_options_rejects_invalid_levels_and_filters() {
+    use magic_wormhole_wasm::init_with_options;
+
+    assert!(init_with_options("verbose".into(), None, None).is_err());
+    let filters: js_sys::Array = ["magic_wormhole=loud"].iter().map(|&filter| wasm_bindgen::JsValue::from_str(filter)).collect();
+    assert!(init_with_options("info".into(), Some(filters), None).is_err());
+    let filters: js_sys::Array = ["=warn"].iter().map(|&filter| wasm_bindgen::JsValue::from_str(filter)).collect();
+    assert!(init_with_options("info".into(), Some(filters), None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn api_controls_are_shared_between_clones() {
+    use magic_wormhole_wasm::api::Controls;
+
+    let controls = Controls::default();
+    let handle = controls.clone();
+    handle.set_paused(true);
+    assert!(controls.is_paused());
+    handle.cancel();
+    assert!(controls.is_cancelled());
+}
+
+#[wasm_bindgen_test]
+fn runtime_is_detected_as_window() {
+    assert_eq!(magic_wormhole_wasm::runtime(), "window");
+}
+
+#[wasm_bindgen_test]
+fn new_session_state_is_empty() {
+    let config = test_config();
+
+    let state = magic_wormhole_wasm::WormholeSession::new(&config).state().unwrap();
+    let field = |name: &str| js_sys::Reflect::get(&state, &name.into()).unwrap();
+    assert!(field("phase").is_null());
+    assert!(field("code").is_null());
+    assert_eq!(field("bytes").as_f64(), Some(0.0));
+}
+
+#[wasm_bindgen_test]
+fn preview_size_must_not_be_zero() {
+    let mut config = test_config();
+
+    assert!(config.set_preview_callback(None, Some(0)).is_err());
+    assert!(config.set_preview_callback(None, Some(1)).is_ok());
+    assert!(config.set_preview_callback(None, None).is_ok());
+}
+
+#[wasm_bindgen_test]
+fn pairing_seed_codes_are_deterministic() {
+    use magic_wormhole_wasm::{PairingSeed, WormholeCode};
+
+    let seed = PairingSeed::from_bytes(&[7; 32]).unwrap();
+    let restored = PairingSeed::from_bytes(&seed.bytes().to_vec()).unwrap();
+    assert_eq!(seed.code(1), restored.code(1));
+    assert_ne!(seed.code(1), seed.code(2));
+
+    let code = WormholeCode::parse(&seed.code(1)).unwrap();
+    assert_eq!(code.nameplate().len(), 6);
+    assert_eq!(code.words().length(), 4);
+    assert!(PairingSeed::from_bytes(&[7; 31]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn require_tls_refuses_plaintext_urls() {
+    let mut config = test_config();
+    assert!(config.set_require_tls(true).is_err());
+
+    let urls = |url: &str| js_sys::Array::of1(&url.into());
+    config.set_rendezvous_urls(urls("https://localhost:4000/v1")).unwrap();
+    config.set_transit_server_urls(urls("wss://localhost:4002")).unwrap();
+    assert!(config.set_require_tls(true).is_ok());
+    assert!(config.set_rendezvous_urls(urls("ws://localhost:4000/v1")).is_err());
+    assert!(config.set_transit_server_urls(urls("http://localhost:4002")).is_err());
+}
+
+#[wasm_bindgen_test]
+fn client_init_rejects_malformed_urls() {
+    let init = |rendezvous_url: &str, transit_server_url: &str| {
+        magic_wormhole_wasm::ClientConfig::client_init(
+            "lothar.com/wormhole/text-or-file-xfer".into(),
+            rendezvous_url.into(),
+            transit_server_url.into(),
+            2,
+        )
+    };
+    assert!(init("ws://localhost:4000/v1", "ws://localhost:4002").is_ok());
+    assert!(init("localhost:4000", "tcp://localhost:4001").is_err());
+    assert!(init("tcp://localhost:4000", "tcp://localhost:4001").is_err());
+    assert!(init("ws://localhost:4000/v1", "tcp://localhost").is_err());
+    let error = init("ws://local host:4000/v1", "tcp://localhost:4001").err().unwrap();
+    assert!(error.as_string().unwrap().contains("ws://local host:4000/v1"));
+}
+
+#[wasm_bindgen_test]
+fn client_config_from_builder_and_options() {
+    use magic_wormhole_wasm::ClientConfig;
+    use wasm_bindgen::JsCast;
+
+    assert!(ClientConfig::builder().build().is_ok());
+    assert!(ClientConfig::builder().rendezvous("ws://localhost:4000/v1".into()).code_length(3).build().is_ok());
+    assert!(ClientConfig::builder().code_length(0).build().is_err());
+    assert!(ClientConfig::builder().require_tls(true).build().is_err());
+
+    let options = |json: &str| js_sys::JSON::parse(json).unwrap().unchecked_into();
+    assert!(ClientConfig::from_js(options("{}")).is_ok());
+    assert!(ClientConfig::from_js(options(r#"{"rendezvous": ["wss://a.example/v1", "wss://b.example/v1"], "codeLength": 3}"#)).is_ok());
+    assert!(ClientConfig::from_js(options(r#"{"rendezvousUrl": "wss://a.example/v1"}"#)).is_err());
+}
+
+#[wasm_bindgen_test]
+fn config_overrides_leave_the_original_alone() {
+    use wasm_bindgen::JsCast;
+
+    let mut config = magic_wormhole_wasm::ClientConfig::builder().code_length(3).build().unwrap();
+    assert_eq!(config.code_length(), 3);
+    assert_eq!(config.appid(), magic_wormhole_wasm::DEFAULT_APPID);
+
+    let options = js_sys::JSON::parse(r#"{"transit": "wss://other.example:443"}"#).unwrap().unchecked_into();
+    let other = config.with_options(options).unwrap();
+    assert_eq!(other.transit_server_urls().get(0).as_string().as_deref(), Some("wss://other.example:443"));
+    assert_eq!(config.transit_server_urls().get(0).as_string().as_deref(), Some(magic_wormhole_wasm::DEFAULT_TRANSIT_SERVER_URL));
+
+    let copy = config.copy();
+    config.set_code_length(2).unwrap();
+    assert_eq!(copy.code_length(), 3);
+    assert!(config.set_code_length(0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn version_names_the_crate() {
+    let version = magic_wormhole_wasm::version().unwrap();
+    let field = |name: &str| js_sys::Reflect::get(&version, &name.into()).unwrap();
+    assert_eq!(field("crate").as_string().as_deref(), Some(env!("CARGO_PKG_VERSION")));
+    assert!(field("magic_wormhole").is_string());
+    assert!(js_sys::Array::is_array(&field("transit_abilities")));
+}
+
+#[wasm_bindgen_test]
+async fn stream_adapters_round_trip() {
+    use futures::io::{AsyncReadExt, AsyncWriteExt};
+    use magic_wormhole_wasm::stream::{self, StreamReader, StreamWriter};
+
+    let data: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+    let (readable, writable) = stream::pipe();
+    let mut writer = StreamWriter::new(&writable);
+    let mut reader = StreamReader::new(&readable);
+    let write = async {
+        for chunk in data.chunks(4096) {
+            writer.write_all(chunk).await.unwrap();
+        }
+        writer.close().await.unwrap();
+    };
+    let mut received = Vec::new();
+    let read = reader.read_to_end(&mut received);
+    let ((), read) = futures::join!(write, read);
+    assert_eq!(read.unwrap(), data.len());
+    assert_eq!(received, data);
+}
+
+#[wasm_bindgen_test]
+async fn zip_stream_round_trips_through_zip_readers() {
+    use futures::io::AsyncReadExt;
+    use magic_wormhole_wasm::archive::{self, PackEntry, ZipStream};
+    use std::io::Read;
+
+    let files: Vec<(&str, Vec<u8>)> = vec![
+        ("notes.txt", b"first entry".to_vec()),
+        ("empty.txt", Vec::new()),
+        ("nested/data.bin", (0..100_000u32).map(|i| (i % 253) as u8).collect()),
+    ];
+    let crc = |data: &[u8]| {
+        let mut crc = flate2::Crc::new();
+        crc.update(data);
+        crc.sum()
+    };
+    let entries = files
+        .iter()
+        .map(|(name, data)| PackEntry { name: name.to_string(), size: data.len() as u64, reader: futures::io::Cursor::new(data.clone()) })
+        .collect();
+    let mut stream = ZipStream::new(entries).unwrap();
+    let size = stream.size();
+    let mut packed = Vec::new();
+    stream.read_to_end(&mut packed).await.unwrap();
+    assert_eq!(packed.len() as u64, size);
+
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(&packed[..])).unwrap();
+    assert_eq!(zip.len(), files.len());
+    for (i, (name, data)) in files.iter().enumerate() {
+        let mut file = zip.by_index(i).unwrap();
+        assert_eq!(file.name(), *name);
+        assert_eq!(file.crc32(), crc(data), "{}", name);
+        let mut read = Vec::new();
+        file.read_to_end(&mut read).unwrap();
+        assert_eq!(&read, data);
+    }
+
+    let unpacked = archive::unpack(&packed, None).unwrap();
+    assert_eq!(unpacked.len(), files.len());
+    for (entry, (name, data)) in unpacked.iter().zip(&files) {
+        assert_eq!(entry.name(), *name);
+        assert_eq!(entry.data().to_vec(), *data);
+        assert_eq!(crc(&entry.data().to_vec()), crc(data));
+    }
+}
+
+#[wasm_bindgen_test]
+async fn archive_index_extracts_single_entries() {
+    use std::io::Write;
+    use zip::write::FileOptions;
+    use zip::CompressionMethod;
+
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    writer.start_file("notes/stored.txt", FileOptions::default().compression_method(CompressionMethod::Stored)).unwrap();
+    writer.write_all(b"kept as it is").unwrap();
+    writer.start_file("deflated.txt", FileOptions::default().compression_method(CompressionMethod::Deflated)).unwrap();
+    writer.write_all(&b"squeezed ".repeat(100)).unwrap();
+    let archive = writer.finish().unwrap().into_inner();
+
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(&archive[..]));
+    let blob = web_sys::Blob::new_with_u8_array_sequence(&parts).unwrap();
+    let index = magic_wormhole_wasm::ArchiveIndex::read(blob).await.unwrap();
+    assert_eq!(js_sys::Array::from(&index.entries().unwrap()).length(), 2);
+
+    let text = |name: &str| {
+        let extracted = index.extract(name.to_owned());
+        async move {
+            let buffer = wasm_bindgen_futures::JsFuture::from(extracted.await.unwrap().array_buffer()).await.unwrap();
+            js_sys::Uint8Array::new(&buffer).to_vec()
+        }
+    };
+    assert_eq!(text("notes/stored.txt").await, b"kept as it is");
+    assert_eq!(text("deflated.txt").await, b"squeezed ".repeat(100));
+    assert!(index.extract("missing.txt".to_owned()).await.is_err());
+}
+
+#[wasm_bindgen_test]
+async fn archive_index_streams_entries_larger_than_a_chunk() {
+    use futures::io::AsyncReadExt;
+    use magic_wormhole_wasm::stream::{self, StreamReader};
+    use std::io::Write;
+    use zip::write::FileOptions;
+    use zip::CompressionMethod;
+
+    let data: Vec<u8> = (0..300_000u32).map(|i| (i * 7 % 251) as u8).collect();
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, method) in [("stored.bin", CompressionMethod::Stored), ("deflated.bin", CompressionMethod::Deflated)] {
+        writer.start_file(name, FileOptions::default().compression_method(method)).unwrap();
+        writer.write_all(&data).unwrap();
+    }
+    let archive = writer.finish().unwrap().into_inner();
+
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(&archive[..]));
+    let blob = web_sys::Blob::new_with_u8_array_sequence(&parts).unwrap();
+    let index = magic_wormhole_wasm::ArchiveIndex::read(blob).await.unwrap();
+    for name in ["stored.bin", "deflated.bin"] {
+        let (readable, writable) = stream::pipe();
+        let mut received = Vec::new();
+        let mut reader = StreamReader::new(&readable);
+        let (extracted, read) = futures::join!(index.extract_to(name.to_owned(), writable), reader.read_to_end(&mut received));
+        extracted.unwrap();
+        read.unwrap();
+        assert_eq!(received, data, "{}", name);
+    }
+}
+
+/// Replaces the global `WebSocket` with a rendezvous server that welcomes,
+/// acks every message and answers `claim` with a mailbox, and returns its
+/// log: the messages sent (`sent`), how often a socket was closed
+/// (`closed`), and `restore()` to put the real `WebSocket` back.
+fn mock_rendezvous() -> wasm_bindgen::JsValue {
+    js_sys::Function::new_no_args(
+        r#"
+        const original = globalThis.WebSocket;
+        const log = { sent: [], closed: 0, restore: () => { globalThis.WebSocket = original; } };
+        class MockWebSocket {
+            constructor(url) {
+                this.url = url;
+                this.readyState = 0;
+                this.binaryType = "blob";
+                setTimeout(() => {
+                    this.readyState = 1;
+                    if (this.onopen) this.onopen({});
+                    this.reply({ type: "welcome", welcome: {} });
+                }, 0);
+            }
+            reply(message) {
+                setTimeout(() => {
+                    if (this.readyState === 1 && this.onmessage) this.onmessage({ data: JSON.stringify(message) });
+                }, 0);
+            }
+            send(data) {
+                const message = JSON.parse(data);
+                log.sent.push(message);
+                this.reply({ type: "ack", id: message.id });
+                if (message.type === "claim") this.reply({ type: "claimed", mailbox: "mock-mailbox" });
+            }
+            close() {
+                if (this.readyState > 1) return;
+                log.closed += 1;
+                this.readyState = 3;
+                setTimeout(() => {
+                    if (this.onclose) this.onclose({ code: 1000, reason: "", wasClean: true });
+                }, 0);
+            }
+        }
+        Object.assign(MockWebSocket, { CONNECTING: 0, OPEN: 1, CLOSING: 2, CLOSED: 3 });
+        globalThis.WebSocket = MockWebSocket;
+        return log;
+        "#,
+    )
+    .call0(&wasm_bindgen::JsValue::NULL)
+    .unwrap()
+}
+
+async fn sleep(ms: u32) {
+    let promise = js_sys::Function::new_with_args("ms", "return new Promise(resolve => setTimeout(resolve, ms))")
+        .call1(&wasm_bindgen::JsValue::NULL, &ms.into())
+        .unwrap();
+    wasm_bindgen_futures::JsFuture::from(js_sys::Promise::from(promise)).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn receive_claims_the_code_and_cancels_against_a_mocked_rendezvous() {
+    use wasm_bindgen::prelude::*;
+
+    let log = mock_rendezvous();
+    let get = |key: &str| js_sys::Reflect::get(&log, &key.into()).unwrap();
+    let sent = || -> Vec<serde_json::Value> { serde_wasm_bindgen::from_value(get("sent")).unwrap() };
+
+    let config = magic_wormhole_wasm::ClientConfig::client_init(
+        "lothar.com/wormhole/text-or-file-xfer".into(),
+        "ws://rendezvous.test/v1".into(),
+        "tcp://relay.test:4001".into(),
+        2,
+    )
+    .unwrap();
+    let session = magic_wormhole_wasm::WormholeSession::new(&config);
+    let errors = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let on_error = {
+        let errors = errors.clone();
+        Closure::<dyn FnMut(String)>::new(move |message| errors.borrow_mut().push(message))
+    };
+    session.on("error".into(), on_error.as_ref().unchecked_ref::<js_sys::Function>().clone());
+
+    let cancel = async {
+        for _ in 0..200 {
+            if sent().iter().any(|message| message["type"] == "add" && message["phase"] == "pake") {
+                break;
+            }
+            sleep(10).await;
+        }
+        session.close();
+    };
+    futures::join!(session.receive("7-guitarist-revenge".into(), None), cancel);
+
+    let sent = sent();
+    let of_type = |kind: &str| sent.iter().find(|message| message["type"] == kind).cloned().unwrap_or_else(|| panic!("no {} in {:?}", kind, sent));
+    assert_eq!(of_type("bind")["appid"], "lothar.com/wormhole/text-or-file-xfer");
+    assert_eq!(of_type("claim")["nameplate"], "7");
+    assert_eq!(of_type("open")["mailbox"], "mock-mailbox");
+    assert_eq!(of_type("add")["phase"], "pake");
+    assert_eq!(errors.borrow().len(), 1, "{:?}", errors.borrow());
+    sleep(10).await;
+    assert_eq!(get("closed").as_f64(), Some(1.0));
+    js_sys::Reflect::get(&log, &"restore".into())
+        .unwrap()
+        .unchecked_into::<js_sys::Function>()
+        .call0(&log)
+        .unwrap();
+}
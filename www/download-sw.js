@@ -0,0 +1,36 @@
+// Service worker side of ClientConfig.receive_stream: answers requests for
+// wormhole-download/<id> with the stream the page posted for that id. See
+// the `download` module of the crate for the message contract.
+
+const downloads = new Map();
+
+self.addEventListener('message', event => {
+    const message = event.data;
+    if (message && message.type === 'wormhole-download') {
+        downloads.set(message.id, message);
+    }
+});
+
+self.addEventListener('fetch', event => {
+    const url = new URL(event.request.url);
+    const prefix = new URL('wormhole-download/', self.registration.scope).pathname;
+    if (!url.pathname.startsWith(prefix)) {
+        return;
+    }
+
+    const id = decodeURIComponent(url.pathname.slice(prefix.length));
+    const download = downloads.get(id);
+    if (!download) {
+        event.respondWith(new Response('Unknown download', { status: 404 }));
+        return;
+    }
+    downloads.delete(id);
+
+    event.respondWith(new Response(download.stream, {
+        headers: {
+            'Content-Type': 'application/octet-stream',
+            'Content-Disposition': `attachment; filename*=UTF-8''${encodeURIComponent(download.filename)}`,
+            'Content-Length': download.filesize.toString(),
+        },
+    }));
+});
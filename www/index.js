@@ -16,48 +16,49 @@ function downloadFile(data, fileName) {
     window.URL.revokeObjectURL(url);
 }
 
+const config = wasm.ClientConfig.builder()
+    .rendezvous("ws://relay.magic-wormhole.io:4000/v1")
+    .transit("ws://piegames.de:4002")
+    .code_length(2)
+    .build();
+
+function confirmVerifier(verifier) {
+    return confirm(`Does your peer see the same emoji?\n\n${verifier.emoji}\n${verifier.sas}`);
+}
+
 (function () {
         wasm.init()
     }
 )();
 
+function createSession() {
+    const session = new wasm.WormholeSession(config);
+    session.on('status', message => codeOutput.innerText = message);
+    session.on('code', code => codeOutput.innerText = `wormhole code:  ${code}`);
+    session.on('verifier', confirmVerifier);
+    session.on('progress', (current, total) => console.log(`Progress: ${current}/${total}`));
+    session.on('wrong-code', () => codeOutput.innerText = "Wrong code, check it and try again");
+    session.on('error', message => codeOutput.innerText = message);
+    return session;
+}
+
 startButton.addEventListener('click', () => {
     const code = codeInput.value;
 
     if (!code) {
         alert("Please enter a code")
     } else {
-        wasm.receive(code, codeOutput)
-            .then(x => {
-                console.log("receiving finished", x);
-                if (x) {
-                    const {data, filename, filesize} = x;
-                    downloadFile(data, filename)
-                }
-            })
+        const session = createSession();
+        session.on('done', result => {
+            console.log("receiving finished", result.filename);
+            downloadFile(result.data(), result.filename)
+        });
+        session.receive(code);
     }
 })
 
-fileInput.addEventListener('change', async (e) => {
-    const config = wasm.create_config(
-        "lothar.com/wormhole/text-or-file-xfer",
-        "ws://relay.magic-wormhole.io:4000/v1",
-        "",
-        2
-    )
-
-    console.log(config instanceof wasm.Config)
-
-    debugger;
-    const jsConnection = await wasm.create_connection(config);
-    debugger;
-    wasm.send_file(config, e.target.files[0].name, jsConnection.wormholeAddress);
-    /*
-    wasm.send(fileInput, codeOutput)
-        .then(x => {
-            console.log("sending finished");
-            })
-            */
+fileInput.addEventListener('change', () => {
+    const session = createSession();
+    session.on('done', () => console.log("sending finished"));
+    session.sendFile(fileInput.files[0]);
 })
-
-
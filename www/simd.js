@@ -0,0 +1,10 @@
+// Whether this browser runs WebAssembly SIMD, for choosing between a build
+// with `RUSTFLAGS="-C target-feature=+simd128" wasm-pack build --out-dir
+// pkg-simd` and the plain one before importing either: a SIMD build fails to
+// compile where it is not supported. The module is the smallest one using a
+// v128 instruction, as in wasm-feature-detect.
+export function simdSupported() {
+    return WebAssembly.validate(new Uint8Array([
+        0, 97, 115, 109, 1, 0, 0, 0, 1, 5, 1, 96, 0, 1, 123, 3, 2, 1, 0, 10, 10, 1, 8, 0, 65, 0, 253, 15, 253, 98, 11,
+    ]));
+}